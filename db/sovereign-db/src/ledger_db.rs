@@ -1,23 +1,29 @@
 use std::{
     collections::HashMap,
+    ops::Range,
     path::Path,
     sync::{Arc, Mutex},
 };
 
 use anyhow::ensure;
-use schemadb::{Schema, DB};
-use sovereign_sdk::{db::SlotStore, services::da::SlotData, stf::Event};
+use schemadb::{Schema, SchemaBatch, DB};
+use sovereign_sdk::{
+    db::SlotStore,
+    services::da::SlotData,
+    stf::{BatchReceipt, Event, EventKey},
+};
 
 use crate::{
     rocks_db_config::gen_rocksdb_options,
     schema::{
         tables::{
-            BatchByHash, BatchByNumber, EventByKey, EventByNumber, SlotByHash, SlotByNumber,
-            TxByHash, TxByNumber, LEDGER_TABLES,
+            BatchByHash, BatchByNumber, EventByKey, EventByModule, EventByNumber, EventNumberByKey,
+            SlotByHash, SlotByNumber, TxByHash, TxByNumber, LEDGER_TABLES,
         },
         types::{
-            BatchNumber, EventNumber, SlotNumber, StoredBatch, StoredSlot, StoredTransaction,
-            TxNumber,
+            BatchIdentifier, BatchNumber, DbBytes, EventGroupIdentifier, EventIdentifier,
+            EventNumber, SlotIdentifier, SlotNumber, Status, StoredBatch, StoredSlot,
+            StoredTransaction, TxIdentifier, TxNumber,
         },
     },
 };
@@ -51,6 +57,57 @@ pub struct SlotCommitBuilder {
     pub batches: Vec<StoredBatch>,
     pub txs: Vec<StoredTransaction>,
     pub events: Vec<Vec<Event>>,
+    next_item_numbers: ItemNumbers,
+}
+
+impl SlotCommitBuilder {
+    /// Creates a builder for the given slot. The items added to the builder are numbered
+    /// starting from `next_item_numbers`, see [`LedgerDB::get_next_items_numbers`].
+    pub fn new(slot: &impl SlotData, next_item_numbers: ItemNumbers) -> Self {
+        let first_batch = BatchNumber(next_item_numbers.batch_number);
+        Self {
+            slot_data: Some(StoredSlot {
+                hash: DbBytes::new(slot.hash().to_vec()),
+                extra_data: DbBytes::new(slot.extra_data_for_storage()),
+                batches: first_batch..first_batch,
+            }),
+            batches: Vec::new(),
+            txs: Vec::new(),
+            events: Vec::new(),
+            next_item_numbers,
+        }
+    }
+
+    /// Adds a batch applied by the state transition function, along with its transactions and events.
+    pub fn add_batch(&mut self, receipt: BatchReceipt) {
+        let first_tx = TxNumber(self.next_item_numbers.tx_number);
+
+        for tx_receipt in receipt.tx_receipts {
+            let first_event = EventNumber(self.next_item_numbers.event_number);
+            self.next_item_numbers.event_number += tx_receipt.events.len() as u64;
+
+            self.txs.push(StoredTransaction {
+                hash: DbBytes::new(tx_receipt.tx_hash.to_vec()),
+                events: first_event..EventNumber(self.next_item_numbers.event_number),
+                data: DbBytes::new(tx_receipt.body),
+                status: tx_receipt.status.into(),
+            });
+            self.events.push(tx_receipt.events);
+            self.next_item_numbers.tx_number += 1;
+        }
+
+        self.batches.push(StoredBatch {
+            hash: DbBytes::new(receipt.batch_hash.to_vec()),
+            extra_data: DbBytes::default(),
+            txs: first_tx..TxNumber(self.next_item_numbers.tx_number),
+            status: Status::Applied,
+        });
+        self.next_item_numbers.batch_number += 1;
+
+        if let Some(slot) = self.slot_data.as_mut() {
+            slot.batches.end = BatchNumber(self.next_item_numbers.batch_number);
+        }
+    }
 }
 
 impl SlotCommitBuilder {
@@ -79,8 +136,6 @@ pub struct SlotCommit {
     pub events: Vec<Vec<Event>>,
 }
 
-impl SlotCommitBuilder {}
-
 impl<S: SlotData> LedgerDB<S> {
     pub fn with_path(path: impl AsRef<Path>) -> Result<Self, anyhow::Error> {
         let path = path.as_ref().join(LEDGER_DB_PATH_SUFFIX);
@@ -99,6 +154,7 @@ impl<S: SlotData> LedgerDB<S> {
             event_number: Self::last_version_written(&inner, EventByNumber)?.unwrap_or_default()
                 + 1,
         };
+        Self::migrate_events_by_key(&inner)?;
 
         Ok(Self {
             db: Arc::new(inner),
@@ -118,72 +174,74 @@ impl<S: SlotData> LedgerDB<S> {
         self.next_item_numbers.lock().unwrap().clone()
     }
 
-    fn put_slot(&self, slot: &StoredSlot, slot_number: &SlotNumber) -> Result<(), anyhow::Error> {
-        self.db.put::<SlotByNumber>(slot_number, slot)?;
-        self.db.put::<SlotByHash>(&slot.hash, slot_number)
+    fn put_slot(
+        batch: &SchemaBatch,
+        slot: &StoredSlot,
+        slot_number: &SlotNumber,
+    ) -> Result<(), anyhow::Error> {
+        batch.put::<SlotByNumber>(slot_number, slot)?;
+        batch.put::<SlotByHash>(&slot.hash, slot_number)
     }
 
     fn put_batch(
-        &self,
-        batch: &StoredBatch,
+        batch: &SchemaBatch,
+        stored_batch: &StoredBatch,
         batch_number: &BatchNumber,
     ) -> Result<(), anyhow::Error> {
-        self.db.put::<BatchByNumber>(batch_number, batch)?;
-        self.db.put::<BatchByHash>(&batch.hash, batch_number)
+        batch.put::<BatchByNumber>(batch_number, stored_batch)?;
+        batch.put::<BatchByHash>(&stored_batch.hash, batch_number)
     }
 
     fn put_transaction(
-        &self,
+        batch: &SchemaBatch,
         tx: &StoredTransaction,
         tx_number: &TxNumber,
     ) -> Result<(), anyhow::Error> {
-        self.db.put::<TxByNumber>(tx_number, tx)?;
-        self.db.put::<TxByHash>(&tx.hash, tx_number)
+        batch.put::<TxByNumber>(tx_number, tx)?;
+        batch.put::<TxByHash>(&tx.hash, tx_number)
     }
 
     fn put_event(
-        &self,
+        batch: &SchemaBatch,
         event: &Event,
         event_number: &EventNumber,
         tx_number: TxNumber,
     ) -> Result<(), anyhow::Error> {
-        self.db.put::<EventByNumber>(event_number, event)?;
-        self.db
-            .put::<EventByKey>(&(event.key.clone(), tx_number, *event_number), &())
+        batch.put::<EventByNumber>(event_number, event)?;
+        if let Some(module) = &event.module {
+            batch.put::<EventByModule>(&(module.address.clone(), tx_number, *event_number), &())?;
+        }
+        batch.put::<EventNumberByKey>(&(event.key.clone(), tx_number, *event_number), &())
     }
 
+    /// Writes the slot with its batches, transactions and events in a single atomic write.
+    /// The next item numbers are advanced only once the write succeeded.
     pub fn commit_slot(&self, data_to_commit: SlotCommit) -> Result<(), anyhow::Error> {
-        // Create a scope to ensure that the lock is released before we commit to the db
-        let item_numbers = {
-            let mut next_item_numbers = self.next_item_numbers.lock().unwrap();
-            let item_numbers = next_item_numbers.clone();
+        // The lock is held until the write is done, so that concurrent commits can't
+        // be numbered from the same counters.
+        let mut next_item_numbers = self.next_item_numbers.lock().unwrap();
+        let item_numbers = next_item_numbers.clone();
 
+        ensure!(
+            item_numbers.batch_number == data_to_commit.slot_data.batches.start.into(),
+            "First batch number must be the next in sequence."
+        );
+        if let Some(first_batch) = data_to_commit.batches.first() {
             ensure!(
-                next_item_numbers.batch_number == data_to_commit.slot_data.batches.start.into(),
-                "First batch number must be the next in sequence."
+                item_numbers.tx_number == first_batch.txs.start.into(),
+                "first ransaction number must be the next in sequence."
             );
-            if let Some(first_batch) = data_to_commit.batches.first() {
-                ensure!(
-                    next_item_numbers.tx_number == first_batch.txs.start.into(),
-                    "first ransaction number must be the next in sequence."
-                );
-            }
-            if let Some(first_tx) = data_to_commit.txs.first() {
-                ensure!(
-                    next_item_numbers.event_number == first_tx.events.start.into(),
-                    "first event number must be the next in sequence."
-                );
-            }
-
-            next_item_numbers.slot_number += 1;
-            next_item_numbers.batch_number += data_to_commit.batches.len() as u64;
-            next_item_numbers.tx_number += data_to_commit.txs.len() as u64;
-            next_item_numbers.event_number += data_to_commit.events.len() as u64;
-            item_numbers
-            // The lock is released here
-        };
+        }
+        if let Some(first_tx) = data_to_commit.txs.first() {
+            ensure!(
+                item_numbers.event_number == first_tx.events.start.into(),
+                "first event number must be the next in sequence."
+            );
+        }
 
-        // Insert data from "bottom up" to ensure consistency is present if the application crashes during insert
+        let batch = SchemaBatch::new();
+        let num_batches = data_to_commit.batches.len() as u64;
+        let num_txs = data_to_commit.txs.len() as u64;
 
         let mut event_number = item_numbers.event_number;
         // Insert transactions and events
@@ -195,24 +253,51 @@ impl<S: SlotData> LedgerDB<S> {
         {
             let tx_number = TxNumber(item_numbers.tx_number + idx as u64);
             for event in event_group.into_iter() {
-                self.put_event(&event, &EventNumber(event_number), tx_number)?;
+                Self::put_event(&batch, &event, &EventNumber(event_number), tx_number)?;
                 event_number += 1;
             }
-            self.put_transaction(&tx, &tx_number)?;
+            Self::put_transaction(&batch, &tx, &tx_number)?;
         }
 
         // Insert batches
-        for (idx, batch) in data_to_commit.batches.into_iter().enumerate() {
+        for (idx, stored_batch) in data_to_commit.batches.into_iter().enumerate() {
             let batch_number = BatchNumber(item_numbers.batch_number + idx as u64);
-            self.put_batch(&batch, &batch_number)?;
+            Self::put_batch(&batch, &stored_batch, &batch_number)?;
         }
 
         // Insert slot
-        self.put_slot(
+        Self::put_slot(
+            &batch,
             &data_to_commit.slot_data,
             &SlotNumber(item_numbers.slot_number),
         )?;
 
+        self.db.write_schemas(batch)?;
+
+        next_item_numbers.slot_number += 1;
+        next_item_numbers.batch_number += num_batches;
+        next_item_numbers.tx_number += num_txs;
+        next_item_numbers.event_number = event_number;
+        Ok(())
+    }
+
+    // The dbs written before `EventNumberByKey` index the events by key in `EventByKey`, whose entries
+    // aren't sorted by number. They are moved to the new index in a single write, so the migration runs only once.
+    fn migrate_events_by_key(db: &DB) -> anyhow::Result<()> {
+        let mut iter = db.iter::<EventByKey>()?;
+        iter.seek_to_first();
+
+        let batch = SchemaBatch::new();
+        let mut migrated = false;
+        for result in iter {
+            let (key, ()) = result?;
+            batch.put::<EventNumberByKey>(&key, &())?;
+            batch.delete::<EventByKey>(&key)?;
+            migrated = true;
+        }
+        if migrated {
+            db.write_schemas(batch)?;
+        }
         Ok(())
     }
 
@@ -231,6 +316,232 @@ impl<S: SlotData> LedgerDB<S> {
     }
 }
 
+/// The maximum number of items returned by a single range or event group query.
+/// Longer ranges are read page by page.
+pub const MAX_PAGE_SIZE: usize = 1_000;
+
+// Read API. The identifiers are resolved to item numbers, which are the primary keys of the ledger.
+impl<S: SlotData> LedgerDB<S> {
+    pub fn resolve_slot(&self, id: &SlotIdentifier) -> Result<Option<SlotNumber>, anyhow::Error> {
+        match id {
+            SlotIdentifier::Hash(hash) => self.db.get::<SlotByHash>(hash),
+            SlotIdentifier::Number(number) => Ok(Some(*number)),
+        }
+    }
+
+    pub fn resolve_batch(
+        &self,
+        id: &BatchIdentifier,
+    ) -> Result<Option<BatchNumber>, anyhow::Error> {
+        match id {
+            BatchIdentifier::Hash(hash) => self.db.get::<BatchByHash>(hash),
+            BatchIdentifier::SlotIdAndIndex((slot_id, index)) => Ok(self
+                .get_slot(slot_id)?
+                .and_then(|slot| Self::nth_in_range(slot.batches, *index).map(BatchNumber))),
+            BatchIdentifier::Number(number) => Ok(Some(*number)),
+        }
+    }
+
+    pub fn resolve_tx(&self, id: &TxIdentifier) -> Result<Option<TxNumber>, anyhow::Error> {
+        match id {
+            TxIdentifier::Hash(hash) => self.db.get::<TxByHash>(hash),
+            TxIdentifier::BatchIdAndIndex((batch_id, index)) => Ok(self
+                .get_batch(batch_id)?
+                .and_then(|batch| Self::nth_in_range(batch.txs, *index).map(TxNumber))),
+            TxIdentifier::Number(number) => Ok(Some(*number)),
+        }
+    }
+
+    /// Resolves [`EventIdentifier::TxIdAndKey`] to the first event of the transaction with the key.
+    pub fn resolve_event(
+        &self,
+        id: &EventIdentifier,
+    ) -> Result<Option<EventNumber>, anyhow::Error> {
+        match id {
+            EventIdentifier::TxIdAndIndex((tx_id, index)) => Ok(self
+                .get_tx(tx_id)?
+                .and_then(|tx| Self::nth_in_range(tx.events, *index).map(EventNumber))),
+            EventIdentifier::TxIdAndKey((tx_id, key)) => {
+                let events = match self.get_tx(tx_id)? {
+                    Some(tx) => tx.events,
+                    None => return Ok(None),
+                };
+                for event_number in events.start.0..events.end.0 {
+                    let event_number = EventNumber(event_number);
+                    if let Some(event) = self.db.get::<EventByNumber>(&event_number)? {
+                        if event.key.as_ref() == key.as_ref() {
+                            return Ok(Some(event_number));
+                        }
+                    }
+                }
+                Ok(None)
+            }
+            EventIdentifier::Number(number) => Ok(Some(*number)),
+        }
+    }
+
+    pub fn get_slot(&self, id: &SlotIdentifier) -> Result<Option<StoredSlot>, anyhow::Error> {
+        match self.resolve_slot(id)? {
+            Some(number) => self.db.get::<SlotByNumber>(&number),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_batch(&self, id: &BatchIdentifier) -> Result<Option<StoredBatch>, anyhow::Error> {
+        match self.resolve_batch(id)? {
+            Some(number) => self.db.get::<BatchByNumber>(&number),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_tx(&self, id: &TxIdentifier) -> Result<Option<StoredTransaction>, anyhow::Error> {
+        match self.resolve_tx(id)? {
+            Some(number) => self.db.get::<TxByNumber>(&number),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_event(&self, id: &EventIdentifier) -> Result<Option<Event>, anyhow::Error> {
+        match self.resolve_event(id)? {
+            Some(number) => self.db.get::<EventByNumber>(&number),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the committed slots in `range`, ordered by number.
+    pub fn get_slots(&self, range: Range<SlotNumber>) -> Result<Vec<StoredSlot>, anyhow::Error> {
+        self.get_range::<SlotByNumber>(range.start.0..range.end.0, SlotNumber)
+    }
+
+    /// Returns the committed batches in `range`, ordered by number.
+    pub fn get_batches(
+        &self,
+        range: Range<BatchNumber>,
+    ) -> Result<Vec<StoredBatch>, anyhow::Error> {
+        self.get_range::<BatchByNumber>(range.start.0..range.end.0, BatchNumber)
+    }
+
+    /// Returns the committed transactions in `range`, ordered by number.
+    pub fn get_txs(&self, range: Range<TxNumber>) -> Result<Vec<StoredTransaction>, anyhow::Error> {
+        self.get_range::<TxByNumber>(range.start.0..range.end.0, TxNumber)
+    }
+
+    /// Returns the committed events in `range`, ordered by number.
+    pub fn get_events(&self, range: Range<EventNumber>) -> Result<Vec<Event>, anyhow::Error> {
+        self.get_range::<EventByNumber>(range.start.0..range.end.0, EventNumber)
+    }
+
+    /// Returns a page of the events in the group, ordered by number: at most `limit` events,
+    /// starting from the event number `start`. The next page starts after the last event returned.
+    pub fn get_event_group(
+        &self,
+        group: &EventGroupIdentifier,
+        start: EventNumber,
+        limit: usize,
+    ) -> Result<Vec<(EventNumber, Event)>, anyhow::Error> {
+        ensure!(
+            limit <= MAX_PAGE_SIZE,
+            "At most {MAX_PAGE_SIZE} events can be read at once"
+        );
+        let event_numbers: Vec<EventNumber> = match group {
+            EventGroupIdentifier::TxId(tx_id) => match self.get_tx(tx_id)? {
+                Some(tx) => (tx.events.start.0.max(start.0)..tx.events.end.0)
+                    .take(limit)
+                    .map(EventNumber)
+                    .collect(),
+                None => Vec::new(),
+            },
+            EventGroupIdentifier::Key(key) => self.event_numbers_by_key(key, start, limit)?,
+        };
+
+        let mut events = Vec::with_capacity(event_numbers.len());
+        for event_number in event_numbers {
+            if let Some(event) = self.db.get::<EventByNumber>(&event_number)? {
+                events.push((event_number, event));
+            }
+        }
+        Ok(events)
+    }
+
+    /// Returns the events emitted by the module with the given address whose numbers are in `range`, ordered by number.
+    /// Like [`LedgerDB::get_events`], the range spans at most [`MAX_PAGE_SIZE`] numbers, longer ranges are read page by page.
+    pub fn get_events_by_module(
+        &self,
+        address: &[u8],
+        range: Range<EventNumber>,
+    ) -> Result<Vec<(EventNumber, Event)>, anyhow::Error> {
+        ensure!(
+            range.end.0.saturating_sub(range.start.0) <= MAX_PAGE_SIZE as u64,
+            "At most {MAX_PAGE_SIZE} items can be read at once"
+        );
+        let address = address.to_vec();
+        let mut iter = self.db.iter::<EventByModule>()?;
+        // The entries of the module are sorted by event number, the transaction number comes after it
+        iter.seek(&(address.clone(), TxNumber(0), range.start))?;
+
+        let mut events = Vec::new();
+        for result in iter {
+            let ((found_address, _, event_number), ()) = result?;
+            if found_address != address || event_number >= range.end {
+                break;
+            }
+            if let Some(event) = self.db.get::<EventByNumber>(&event_number)? {
+                events.push((event_number, event));
+            }
+        }
+        Ok(events)
+    }
+
+    // Returns at most `limit` numbers of the events with the key, starting from `start`.
+    fn event_numbers_by_key(
+        &self,
+        key: &[u8],
+        start: EventNumber,
+        limit: usize,
+    ) -> Result<Vec<EventNumber>, anyhow::Error> {
+        let event_key = EventKey::from(key.to_vec());
+        let mut iter = self.db.iter::<EventNumberByKey>()?;
+        // The entries of the key are sorted by event number, the transaction number comes after it
+        iter.seek(&(event_key.clone(), TxNumber(0), start))?;
+
+        let mut event_numbers = Vec::with_capacity(limit);
+        for result in iter.take(limit) {
+            let ((found_key, _, event_number), ()) = result?;
+            if found_key != event_key {
+                break;
+            }
+            event_numbers.push(event_number);
+        }
+        Ok(event_numbers)
+    }
+
+    // Items are numbered consecutively, so the first missing number is past the latest item.
+    fn get_range<T: Schema>(
+        &self,
+        range: Range<u64>,
+        key: impl Fn(u64) -> T::Key,
+    ) -> Result<Vec<T::Value>, anyhow::Error> {
+        ensure!(
+            range.end.saturating_sub(range.start) <= MAX_PAGE_SIZE as u64,
+            "At most {MAX_PAGE_SIZE} items can be read at once"
+        );
+        let mut items = Vec::with_capacity(range.end.saturating_sub(range.start) as usize);
+        for number in range {
+            match self.db.get::<T>(&key(number))? {
+                Some(item) => items.push(item),
+                None => break,
+            }
+        }
+        Ok(items)
+    }
+
+    // Returns the `index`th number of the range, if it is in the range.
+    fn nth_in_range<N: Into<u64>>(range: Range<N>, index: u64) -> Option<u64> {
+        let (start, end): (u64, u64) = (range.start.into(), range.end.into());
+        start.checked_add(index).filter(|number| *number < end)
+    }
+}
+
 impl<S: SlotData> SlotStore for LedgerDB<S> {
     type Slot = S;
 
@@ -245,3 +556,508 @@ impl<S: SlotData> SlotStore for LedgerDB<S> {
             .insert(hash, slot_data);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use sovereign_sdk::stf::{EventModule, TransactionReceipt, TxStatus};
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Clone, BorshSerialize, BorshDeserialize)]
+    struct MockSlot([u8; 32]);
+
+    impl SlotData for MockSlot {
+        type BatchData = ();
+
+        fn extra_data_for_storage(&self) -> Vec<u8> {
+            vec![]
+        }
+
+        fn reconstruct_from_storage(_extra_data: &[u8], _batches: Vec<Self::BatchData>) -> Self {
+            unimplemented!()
+        }
+
+        fn hash(&self) -> [u8; 32] {
+            self.0
+        }
+    }
+
+    fn tx_receipt(tx_hash: u8, status: TxStatus, events: Vec<Event>) -> TransactionReceipt {
+        TransactionReceipt {
+            tx_hash: [tx_hash; 32],
+            body: vec![tx_hash],
+            status,
+            events,
+            error: None,
+            gas_used: 0,
+        }
+    }
+
+    #[test]
+    fn test_commit_slots_from_receipts() {
+        let ledger_db = LedgerDB::<MockSlot>::temporary();
+
+        for slot in 1..=2u8 {
+            let mut builder =
+                SlotCommitBuilder::new(&MockSlot([slot; 32]), ledger_db.get_next_items_numbers());
+            builder.add_batch(BatchReceipt {
+                batch_hash: [slot; 32],
+                tx_receipts: vec![
+                    tx_receipt(
+                        10 * slot,
+                        TxStatus::Applied,
+                        vec![Event::new("key", "a"), Event::new("key", "b")],
+                    ),
+                    tx_receipt(10 * slot + 1, TxStatus::Reverted, vec![]),
+                ],
+            });
+            ledger_db.commit_slot(builder.finalize().unwrap()).unwrap();
+        }
+
+        let numbers = ledger_db.get_next_items_numbers();
+        assert_eq!(numbers.slot_number, 3);
+        assert_eq!(numbers.batch_number, 3);
+        assert_eq!(numbers.tx_number, 5);
+        assert_eq!(numbers.event_number, 5);
+
+        let slot = ledger_db
+            .db
+            .get::<SlotByNumber>(&SlotNumber(2))
+            .unwrap()
+            .unwrap();
+        assert_eq!(slot.batches, BatchNumber(2)..BatchNumber(3));
+
+        let batch = ledger_db
+            .db
+            .get::<BatchByNumber>(&BatchNumber(2))
+            .unwrap()
+            .unwrap();
+        assert_eq!(batch.txs, TxNumber(3)..TxNumber(5));
+
+        let applied = ledger_db
+            .db
+            .get::<TxByNumber>(&TxNumber(3))
+            .unwrap()
+            .unwrap();
+        assert_eq!(applied.hash, DbBytes::new(vec![20; 32]));
+        assert_eq!(applied.data, DbBytes::new(vec![20]));
+        assert_eq!(applied.events, EventNumber(3)..EventNumber(5));
+        assert_eq!(applied.status, Status::Applied);
+
+        let reverted = ledger_db
+            .db
+            .get::<TxByNumber>(&TxNumber(4))
+            .unwrap()
+            .unwrap();
+        assert_eq!(reverted.events, EventNumber(5)..EventNumber(5));
+        assert_eq!(reverted.status, Status::Reverted);
+    }
+
+    #[test]
+    fn test_event_group_pages_follow_event_numbers() {
+        let ledger_db = LedgerDB::<MockSlot>::temporary();
+
+        // Enough events for their numbers to span several bytes, with a longer key
+        // sharing the prefix of the queried one.
+        let events = (0..300)
+            .map(|i| match i % 3 {
+                0 => Event::new("key2", "c"),
+                _ => Event::new("key", &i.to_string()),
+            })
+            .collect();
+        let mut builder =
+            SlotCommitBuilder::new(&MockSlot([1; 32]), ledger_db.get_next_items_numbers());
+        builder.add_batch(BatchReceipt {
+            batch_hash: [1; 32],
+            tx_receipts: vec![tx_receipt(1, TxStatus::Applied, events)],
+        });
+        ledger_db.commit_slot(builder.finalize().unwrap()).unwrap();
+
+        let group = EventGroupIdentifier::Key(b"key".to_vec());
+        let page = ledger_db
+            .get_event_group(&group, EventNumber(255), 4)
+            .unwrap();
+        // Event i is numbered i + 1, the events of "key2" are skipped.
+        assert_eq!(
+            page,
+            vec![
+                (EventNumber(255), Event::new("key", "254")),
+                (EventNumber(257), Event::new("key", "256")),
+                (EventNumber(258), Event::new("key", "257")),
+                (EventNumber(260), Event::new("key", "259")),
+            ]
+        );
+        let page = ledger_db
+            .get_event_group(&group, EventNumber(300), 4)
+            .unwrap();
+        assert_eq!(page, vec![(EventNumber(300), Event::new("key", "299"))]);
+    }
+
+    #[test]
+    fn test_events_are_indexed_by_module() {
+        let ledger_db = LedgerDB::<MockSlot>::temporary();
+        let module = EventModule {
+            name: "bank".to_owned(),
+            address: vec![7; 32],
+        };
+
+        let mut builder =
+            SlotCommitBuilder::new(&MockSlot([1; 32]), ledger_db.get_next_items_numbers());
+        builder.add_batch(BatchReceipt {
+            batch_hash: [1; 32],
+            tx_receipts: vec![tx_receipt(
+                1,
+                TxStatus::Applied,
+                vec![
+                    Event::new("transfer", "a").with_module(module.clone()),
+                    Event::new("key", "b"),
+                ],
+            )],
+        });
+        ledger_db.commit_slot(builder.finalize().unwrap()).unwrap();
+
+        let event = ledger_db
+            .db
+            .get::<EventByNumber>(&EventNumber(1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.module, Some(module.clone()));
+
+        let by_module = |event_number| {
+            ledger_db
+                .db
+                .get::<EventByModule>(&(module.address.clone(), TxNumber(1), event_number))
+                .unwrap()
+        };
+        assert_eq!(by_module(EventNumber(1)), Some(()));
+        // Events without a module aren't indexed by module.
+        assert_eq!(by_module(EventNumber(2)), None);
+    }
+
+    #[test]
+    fn test_events_by_module_pages() {
+        let ledger_db = LedgerDB::<MockSlot>::temporary();
+        let module = |address| EventModule {
+            name: "bank".to_owned(),
+            address: vec![address; 32],
+        };
+
+        // Enough events for their numbers to span several bytes, with another module interleaved.
+        let events = (0..300)
+            .map(|i| match i % 3 {
+                0 => Event::new("key", "c").with_module(module(8)),
+                _ => Event::new("key", &i.to_string()).with_module(module(7)),
+            })
+            .collect();
+        let mut builder =
+            SlotCommitBuilder::new(&MockSlot([1; 32]), ledger_db.get_next_items_numbers());
+        builder.add_batch(BatchReceipt {
+            batch_hash: [1; 32],
+            tx_receipts: vec![tx_receipt(1, TxStatus::Applied, events)],
+        });
+        ledger_db.commit_slot(builder.finalize().unwrap()).unwrap();
+
+        // Event i is numbered i + 1, the events of the other module are skipped.
+        let page = ledger_db
+            .get_events_by_module(&[7; 32], EventNumber(255)..EventNumber(259))
+            .unwrap();
+        let with_module = |value: &str| Event::new("key", value).with_module(module(7));
+        assert_eq!(
+            page,
+            vec![
+                (EventNumber(255), with_module("254")),
+                (EventNumber(257), with_module("256")),
+                (EventNumber(258), with_module("257")),
+            ]
+        );
+        let page = ledger_db
+            .get_events_by_module(&[7; 32], EventNumber(300)..EventNumber(400))
+            .unwrap();
+        assert_eq!(page, vec![(EventNumber(300), with_module("299"))]);
+        assert!(ledger_db
+            .get_events_by_module(&[9; 32], EventNumber(1)..EventNumber(301))
+            .unwrap()
+            .is_empty());
+
+        let too_long = EventNumber(1)..EventNumber(MAX_PAGE_SIZE as u64 + 2);
+        assert!(ledger_db.get_events_by_module(&[7; 32], too_long).is_err());
+    }
+
+    #[test]
+    fn test_migrate_events_by_key() {
+        let path = schemadb::temppath::TempPath::new();
+        {
+            let ledger_db = LedgerDB::<MockSlot>::with_path(&path).unwrap();
+            let mut builder =
+                SlotCommitBuilder::new(&MockSlot([1; 32]), ledger_db.get_next_items_numbers());
+            builder.add_batch(BatchReceipt {
+                batch_hash: [1; 32],
+                tx_receipts: vec![tx_receipt(
+                    1,
+                    TxStatus::Applied,
+                    vec![Event::new("key", "a"), Event::new("key", "b")],
+                )],
+            });
+            ledger_db.commit_slot(builder.finalize().unwrap()).unwrap();
+
+            // A db written before `EventNumberByKey` indexes the events in `EventByKey`.
+            let batch = SchemaBatch::new();
+            for number in 1..=2 {
+                let key = (
+                    EventKey::from(b"key".to_vec()),
+                    TxNumber(1),
+                    EventNumber(number),
+                );
+                batch.delete::<EventNumberByKey>(&key).unwrap();
+                batch.put::<EventByKey>(&key, &()).unwrap();
+            }
+            ledger_db.db.write_schemas(batch).unwrap();
+        }
+
+        let ledger_db = LedgerDB::<MockSlot>::with_path(&path).unwrap();
+        let group = EventGroupIdentifier::Key(b"key".to_vec());
+        assert_eq!(
+            ledger_db
+                .get_event_group(&group, EventNumber(2), MAX_PAGE_SIZE)
+                .unwrap(),
+            vec![(EventNumber(2), Event::new("key", "b"))]
+        );
+        let mut legacy = ledger_db.db.iter::<EventByKey>().unwrap();
+        legacy.seek_to_first();
+        assert!(legacy.next().is_none());
+    }
+
+    #[test]
+    fn test_failed_write_writes_nothing() {
+        // The slot tables are written last, a db without one of them fails the write
+        // after the transactions and events are encoded.
+        let path = schemadb::temppath::TempPath::new();
+        let db = DB::open(
+            &path,
+            "ledger-db",
+            LEDGER_TABLES
+                .iter()
+                .copied()
+                .filter(|table| *table != SlotByHash::table_name()),
+            &gen_rocksdb_options(&Default::default(), false),
+        )
+        .unwrap();
+        // Numbered as an empty db
+        let item_numbers = ItemNumbers {
+            slot_number: 1,
+            batch_number: 1,
+            tx_number: 1,
+            event_number: 1,
+        };
+        let ledger_db = LedgerDB::<MockSlot> {
+            db: Arc::new(db),
+            slots_to_execute: Default::default(),
+            next_item_numbers: Arc::new(Mutex::new(item_numbers.clone())),
+        };
+
+        let mut builder = SlotCommitBuilder::new(&MockSlot([1; 32]), item_numbers.clone());
+        builder.add_batch(BatchReceipt {
+            batch_hash: [1; 32],
+            tx_receipts: vec![tx_receipt(
+                1,
+                TxStatus::Applied,
+                vec![Event::new("key", "a")],
+            )],
+        });
+        assert!(ledger_db.commit_slot(builder.finalize().unwrap()).is_err());
+
+        let numbers = ledger_db.get_next_items_numbers();
+        assert_eq!(numbers.slot_number, item_numbers.slot_number);
+        assert_eq!(numbers.batch_number, item_numbers.batch_number);
+        assert_eq!(numbers.tx_number, item_numbers.tx_number);
+        assert_eq!(numbers.event_number, item_numbers.event_number);
+
+        assert_eq!(
+            ledger_db.db.get::<EventByNumber>(&EventNumber(1)).unwrap(),
+            None
+        );
+        assert_eq!(ledger_db.db.get::<TxByNumber>(&TxNumber(1)).unwrap(), None);
+        assert_eq!(
+            ledger_db.db.get::<BatchByNumber>(&BatchNumber(1)).unwrap(),
+            None
+        );
+        assert_eq!(
+            ledger_db.db.get::<SlotByNumber>(&SlotNumber(1)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_rejected_commit_writes_nothing() {
+        let path = schemadb::temppath::TempPath::new();
+        let ledger_db = LedgerDB::<MockSlot>::with_path(&path).unwrap();
+        let slot_commit = |slot: u8, item_numbers| {
+            let mut builder = SlotCommitBuilder::new(&MockSlot([slot; 32]), item_numbers);
+            builder.add_batch(BatchReceipt {
+                batch_hash: [slot; 32],
+                tx_receipts: vec![tx_receipt(
+                    slot,
+                    TxStatus::Applied,
+                    vec![Event::new("key", "a")],
+                )],
+            });
+            builder.finalize().unwrap()
+        };
+
+        let item_numbers = ledger_db.get_next_items_numbers();
+        ledger_db
+            .commit_slot(slot_commit(1, item_numbers.clone()))
+            .unwrap();
+        let committed_numbers = ledger_db.get_next_items_numbers();
+
+        // Numbered from the counters of the previous slot.
+        assert!(ledger_db.commit_slot(slot_commit(2, item_numbers)).is_err());
+        let numbers = ledger_db.get_next_items_numbers();
+        assert_eq!(numbers.slot_number, committed_numbers.slot_number);
+        assert_eq!(numbers.event_number, committed_numbers.event_number);
+        assert_eq!(
+            ledger_db
+                .db
+                .get::<SlotByHash>(&DbBytes::new(vec![2; 32]))
+                .unwrap(),
+            None
+        );
+
+        // The counters recovered on restart match the committed slots.
+        drop(ledger_db);
+        let ledger_db = LedgerDB::<MockSlot>::with_path(&path).unwrap();
+        let numbers = ledger_db.get_next_items_numbers();
+        assert_eq!(numbers.slot_number, committed_numbers.slot_number);
+        assert_eq!(numbers.batch_number, committed_numbers.batch_number);
+        assert_eq!(numbers.tx_number, committed_numbers.tx_number);
+        assert_eq!(numbers.event_number, committed_numbers.event_number);
+    }
+
+    #[test]
+    fn test_read_api() {
+        let ledger_db = LedgerDB::<MockSlot>::temporary();
+        for slot in 1..=2u8 {
+            let mut builder =
+                SlotCommitBuilder::new(&MockSlot([slot; 32]), ledger_db.get_next_items_numbers());
+            builder.add_batch(BatchReceipt {
+                batch_hash: [slot; 32],
+                tx_receipts: vec![
+                    tx_receipt(
+                        10 * slot,
+                        TxStatus::Applied,
+                        vec![Event::new("key", "a"), Event::new("key", "b")],
+                    ),
+                    tx_receipt(10 * slot + 1, TxStatus::Reverted, vec![]),
+                ],
+            });
+            ledger_db.commit_slot(builder.finalize().unwrap()).unwrap();
+        }
+        let hash = |byte: u8| DbBytes::new(vec![byte; 32]);
+
+        let slot = ledger_db.get_slot(&SlotIdentifier::Hash(hash(2))).unwrap();
+        assert_eq!(slot.unwrap().batches, BatchNumber(2)..BatchNumber(3));
+        assert!(ledger_db
+            .get_slot(&SlotIdentifier::Number(SlotNumber(3)))
+            .unwrap()
+            .is_none());
+
+        let batch = ledger_db
+            .get_batch(&BatchIdentifier::SlotIdAndIndex((
+                SlotIdentifier::Number(SlotNumber(2)),
+                0,
+            )))
+            .unwrap();
+        assert_eq!(batch.unwrap().hash, hash(2));
+        assert!(ledger_db
+            .get_batch(&BatchIdentifier::SlotIdAndIndex((
+                SlotIdentifier::Number(SlotNumber(2)),
+                1,
+            )))
+            .unwrap()
+            .is_none());
+
+        let tx = ledger_db
+            .get_tx(&TxIdentifier::BatchIdAndIndex((
+                BatchIdentifier::Hash(hash(2)),
+                1,
+            )))
+            .unwrap()
+            .unwrap();
+        assert_eq!(tx.hash, hash(21));
+        assert_eq!(tx.status, Status::Reverted);
+        let tx = ledger_db.get_tx(&TxIdentifier::Hash(hash(10))).unwrap();
+        assert_eq!(tx.unwrap().events, EventNumber(1)..EventNumber(3));
+
+        let event = ledger_db
+            .get_event(&EventIdentifier::TxIdAndIndex((
+                TxIdentifier::Number(TxNumber(3)),
+                1,
+            )))
+            .unwrap();
+        assert_eq!(event, Some(Event::new("key", "b")));
+        let by_key = |key: &str| {
+            ledger_db
+                .resolve_event(&EventIdentifier::TxIdAndKey((
+                    TxIdentifier::Hash(hash(10)),
+                    DbBytes::new(key.as_bytes().to_vec()),
+                )))
+                .unwrap()
+        };
+        assert_eq!(by_key("key"), Some(EventNumber(1)));
+        assert_eq!(by_key("other"), None);
+
+        // Ranges stop at the latest item.
+        assert_eq!(
+            ledger_db
+                .get_slots(SlotNumber(1)..SlotNumber(10))
+                .unwrap()
+                .len(),
+            2
+        );
+        let txs = ledger_db.get_txs(TxNumber(2)..TxNumber(4)).unwrap();
+        assert_eq!(
+            txs.into_iter().map(|tx| tx.hash).collect::<Vec<_>>(),
+            vec![hash(11), hash(20)]
+        );
+        assert_eq!(
+            ledger_db
+                .get_events(EventNumber(2)..EventNumber(4))
+                .unwrap(),
+            vec![Event::new("key", "b"), Event::new("key", "a")]
+        );
+        assert!(ledger_db
+            .get_events(EventNumber(0)..EventNumber(MAX_PAGE_SIZE as u64 + 1))
+            .is_err());
+
+        // The events of a key are read page by page.
+        let group = EventGroupIdentifier::Key(b"key".to_vec());
+        let page = ledger_db
+            .get_event_group(&group, EventNumber(0), 3)
+            .unwrap();
+        let numbers: Vec<_> = page.iter().map(|(number, _)| *number).collect();
+        assert_eq!(
+            numbers,
+            vec![EventNumber(1), EventNumber(2), EventNumber(3)]
+        );
+        let page = ledger_db
+            .get_event_group(&group, EventNumber(4), 3)
+            .unwrap();
+        assert_eq!(page, vec![(EventNumber(4), Event::new("key", "b"))]);
+
+        let group = EventGroupIdentifier::TxId(TxIdentifier::Number(TxNumber(3)));
+        let page = ledger_db
+            .get_event_group(&group, EventNumber(0), 10)
+            .unwrap();
+        assert_eq!(
+            page,
+            vec![
+                (EventNumber(3), Event::new("key", "a")),
+                (EventNumber(4), Event::new("key", "b"))
+            ]
+        );
+        assert!(ledger_db
+            .get_event_group(&group, EventNumber(0), MAX_PAGE_SIZE + 1)
+            .is_err());
+    }
+}
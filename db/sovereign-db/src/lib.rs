@@ -1,6 +1,7 @@
 use state_db::StateDB;
 
 pub mod ledger_db;
+pub mod pruner;
 pub mod rocks_db_config;
 pub mod schema;
 pub mod state_db;
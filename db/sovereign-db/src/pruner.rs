@@ -0,0 +1,175 @@
+//! Deletion of the state which fell out of the retention window.
+//!
+//! Every commit of the JMT makes some nodes and some rows of `JmtValues` stale: they are only needed to read
+//! the versions before that commit. A `StateDB` opened with pruning indexes them by the version which made them
+//! stale, and the pruner deletes them in a background thread once every version which needs them is outside of
+//! the window.
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    thread::JoinHandle,
+};
+
+use jmt::Version;
+use schemadb::{SchemaBatch, DB};
+
+use crate::schema::tables::{JmtNodes, JmtValues, StaleNodes, StaleValues};
+
+/// The number of stale entries deleted with a single write.
+const PRUNING_BATCH_SIZE: usize = 1_000;
+
+/// Configuration of the state pruning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PruningConfig {
+    /// The number of latest versions which stay readable, at least 1.
+    pub retention_window: u64,
+}
+
+#[derive(Default)]
+struct Progress {
+    /// The entries which became stale at or before this version can be deleted.
+    target: Version,
+    /// The entries which became stale at or before this version were deleted.
+    pruned: Version,
+    /// The pruning up to this target failed, it is retried once the target moves.
+    failed: Option<Version>,
+    shutdown: bool,
+}
+
+pub(crate) struct StatePruner {
+    config: PruningConfig,
+    progress: Arc<(Mutex<Progress>, Condvar)>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl StatePruner {
+    pub(crate) fn start(db: Arc<DB>, config: PruningConfig) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            config.retention_window > 0,
+            "The retention window must keep at least one version"
+        );
+        let progress = Arc::new((Mutex::new(Progress::default()), Condvar::new()));
+        let worker = {
+            let progress = progress.clone();
+            std::thread::Builder::new()
+                .name("state-pruner".to_owned())
+                .spawn(move || Self::run(&db, &progress))?
+        };
+
+        Ok(Self {
+            config,
+            progress,
+            worker: Some(worker),
+        })
+    }
+
+    /// Returns the oldest version which stays readable once `latest_version` is committed.
+    pub(crate) fn oldest_version(&self, latest_version: Version) -> Version {
+        latest_version.saturating_sub(self.config.retention_window - 1)
+    }
+
+    /// Schedules the pruning of the versions which fell out of the window, without waiting for it.
+    pub(crate) fn on_commit(&self, latest_version: Version) {
+        let (lock, condvar) = &*self.progress;
+        let mut progress = lock.lock().unwrap();
+        progress.target = progress.target.max(self.oldest_version(latest_version));
+        condvar.notify_all();
+    }
+
+    /// Blocks until all the scheduled pruning is done, or failed.
+    pub(crate) fn wait(&self) {
+        let (lock, condvar) = &*self.progress;
+        let _progress = condvar
+            .wait_while(lock.lock().unwrap(), |progress| {
+                progress.pruned < progress.target && progress.failed != Some(progress.target)
+            })
+            .unwrap();
+    }
+
+    fn run(db: &DB, progress: &(Mutex<Progress>, Condvar)) {
+        let (lock, condvar) = progress;
+        loop {
+            let target = {
+                let progress = condvar
+                    .wait_while(lock.lock().unwrap(), |progress| {
+                        (progress.pruned >= progress.target
+                            || progress.failed == Some(progress.target))
+                            && !progress.shutdown
+                    })
+                    .unwrap();
+                if progress.shutdown {
+                    return;
+                }
+                progress.target
+            };
+
+            // The index entries are deleted together with the data, so if a pass fails
+            // the entries it didn't delete are retried by the next one.
+            let result = Self::prune(db, target);
+
+            let mut progress = lock.lock().unwrap();
+            match result {
+                Ok(()) => {
+                    progress.pruned = target;
+                    progress.failed = None;
+                }
+                Err(e) => {
+                    tracing::error!("Failed to prune the state up to version {target}: {e:?}");
+                    progress.failed = Some(target);
+                }
+            }
+            condvar.notify_all();
+        }
+    }
+
+    /// Deletes the nodes and values which became stale at or before `version`.
+    fn prune(db: &DB, version: Version) -> anyhow::Result<()> {
+        let mut batch = SchemaBatch::new();
+        let mut batch_len = 0;
+
+        let mut stale_nodes = db.iter::<StaleNodes>()?;
+        stale_nodes.seek_to_first();
+        for result in stale_nodes {
+            let (index, ()) = result?;
+            // The index is sorted by the version which made the nodes stale
+            if index.stale_since_version > version {
+                break;
+            }
+            batch.delete::<JmtNodes>(&index.node_key)?;
+            batch.delete::<StaleNodes>(&index)?;
+            batch_len += 1;
+            if batch_len % PRUNING_BATCH_SIZE == 0 {
+                db.write_schemas(std::mem::take(&mut batch))?;
+            }
+        }
+
+        let mut stale_values = db.iter::<StaleValues>()?;
+        stale_values.seek_to_first();
+        for result in stale_values {
+            let ((stale_since_version, key, key_version), ()) = result?;
+            if stale_since_version > version {
+                break;
+            }
+            batch.delete::<JmtValues>(&(key.clone(), key_version))?;
+            batch.delete::<StaleValues>(&(stale_since_version, key, key_version))?;
+            batch_len += 1;
+            if batch_len % PRUNING_BATCH_SIZE == 0 {
+                db.write_schemas(std::mem::take(&mut batch))?;
+            }
+        }
+
+        db.write_schemas(batch)
+    }
+}
+
+impl Drop for StatePruner {
+    fn drop(&mut self) {
+        {
+            let (lock, condvar) = &*self.progress;
+            lock.lock().unwrap().shutdown = true;
+            condvar.notify_all();
+        }
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
@@ -13,8 +13,18 @@
 //! - TxHash -> TxNumber
 //!
 //! Event Tables:
-//! - (EventKey, TxNumber) -> EventNumber
+//! - (EventKey, EventNumber, TxNumber) -> ()
+//! - (ModuleAddress, EventNumber, TxNumber) -> ()
 //! - EventNumber -> (EventKey, EventValue)
+//!
+//! State Tables:
+//! - KeyHash -> StateKey
+//! - (StateKey, Version) -> JmtValue
+//! - StateKey -> ()
+//! - NodeKey -> Node
+//! - (StaleSinceVersion, NodeKey) -> ()
+//! - (StaleSinceVersion, StateKey, Version) -> ()
+//! - Version -> ()
 
 use super::types::{
     BatchNumber, DbHash, EventNumber, JmtValue, SlotNumber, StateKey, StoredBatch, StoredSlot,
@@ -24,7 +34,7 @@ use super::types::{
 use borsh::maybestd;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use jmt::{
-    storage::{Node, NodeKey},
+    storage::{Node, NodeKey, StaleNodeIndex},
     Version,
 };
 use sovereign_sdk::{
@@ -36,7 +46,11 @@ use sovereign_sdk::{
 pub const STATE_TABLES: &[&str] = &[
     KeyHashToKey::table_name(),
     JmtValues::table_name(),
+    StateKeys::table_name(),
     JmtNodes::table_name(),
+    StaleNodes::table_name(),
+    StaleValues::table_name(),
+    CommittedVersions::table_name(),
 ];
 
 pub const LEDGER_TABLES: &[&str] = &[
@@ -47,6 +61,8 @@ pub const LEDGER_TABLES: &[&str] = &[
     TxByHash::table_name(),
     TxByNumber::table_name(),
     EventByKey::table_name(),
+    EventNumberByKey::table_name(),
+    EventByModule::table_name(),
     EventByNumber::table_name(),
 ];
 
@@ -172,11 +188,67 @@ define_table_with_default_codec!(
     (EventByNumber) EventNumber => Event
 );
 
+/// Defines a "secondary index" of the events sharing a prefix, e.g. their key, by event number.
+/// Its keys are the prefix followed by the big-endian event and transaction numbers, so that the entries
+/// of a prefix are sorted by event number and can be read from any of them. Events are numbered in the
+/// order of their transactions, so the entries are sorted by transaction number as well.
+macro_rules! define_event_index {
+    ($(#[$docs:meta])+ ($table_name:ident) $prefix:ty) => {
+        define_table_without_codec!($(#[$docs])+ ( $table_name ) ($prefix, TxNumber, EventNumber) => ());
+
+        impl KeyEncoder<$table_name> for ($prefix, TxNumber, EventNumber) {
+            fn encode_key(&self) -> sovereign_sdk::db::Result<Vec<u8>> {
+                let prefix_len = AsRef::<[u8]>::as_ref(&self.0).len();
+                let mut out = Vec::with_capacity(prefix_len + 2 * std::mem::size_of::<u64>() + 4);
+                self.0.encode(&mut out);
+                out.write_u64::<BigEndian>(self.2 .0)
+                    .expect("serialization to vec is infallible");
+                out.write_u64::<BigEndian>(self.1 .0)
+                    .expect("serialization to vec is infallible");
+                Ok(out)
+            }
+        }
+
+        impl KeyDecoder<$table_name> for ($prefix, TxNumber, EventNumber) {
+            fn decode_key(data: &[u8]) -> sovereign_sdk::db::Result<Self> {
+                let mut cursor = maybestd::io::Cursor::new(data);
+                let prefix = <$prefix>::decode(&mut cursor)?;
+                let event_number = cursor.read_u64::<BigEndian>()?;
+                let tx_number = cursor.read_u64::<BigEndian>()?;
+                Ok((prefix, TxNumber(tx_number), EventNumber(event_number)))
+            }
+        }
+
+        impl ValueCodec<$table_name> for () {
+            fn encode_value(&self) -> sovereign_sdk::db::Result<Vec<u8>> {
+                Ok(Vec::new())
+            }
+
+            fn decode_value(_data: &[u8]) -> sovereign_sdk::db::Result<Self> {
+                Ok(())
+            }
+        }
+    };
+}
+
 define_table_with_default_codec!(
-    /// A "secondary index" for event data by key
+    /// A "secondary index" for event data by key, in the layout of the dbs written before [`EventNumberByKey`].
+    /// Its numbers are little-endian, so its entries aren't sorted by number. It is only read to migrate its
+    /// entries when the db is opened.
     (EventByKey) (EventKey, TxNumber, EventNumber) => ()
 );
 
+define_event_index!(
+    /// A "secondary index" for event data by key
+    (EventNumberByKey) EventKey
+);
+
+define_event_index!(
+    /// A "secondary index" for event data by the address of the module which emitted it.
+    /// Events which weren't emitted by a module aren't indexed.
+    (EventByModule) Vec<u8>
+);
+
 define_table_without_codec!(
     /// The source of truth for JMT nodes
     (JmtNodes) NodeKey => Node
@@ -239,6 +311,34 @@ impl ValueCodec<JmtValues> for JmtValue {
     }
 }
 
+define_table_without_codec!(
+    /// Every key ever written to `JmtValues`. The keys are stored as raw bytes, without the length prefix
+    /// of `JmtValues`, so that the keys sharing a prefix are contiguous and can be found with a seek.
+    (StateKeys) StateKey => ()
+);
+
+impl KeyEncoder<StateKeys> for StateKey {
+    fn encode_key(&self) -> sovereign_sdk::db::Result<Vec<u8>> {
+        Ok(self.clone())
+    }
+}
+
+impl KeyDecoder<StateKeys> for StateKey {
+    fn decode_key(data: &[u8]) -> sovereign_sdk::db::Result<Self> {
+        Ok(data.to_vec())
+    }
+}
+
+impl ValueCodec<StateKeys> for () {
+    fn encode_value(&self) -> sovereign_sdk::db::Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+
+    fn decode_value(_data: &[u8]) -> sovereign_sdk::db::Result<Self> {
+        Ok(())
+    }
+}
+
 define_table_with_default_codec!(
     /// A mapping from key-hashes to their preimages and latest version. Since we store raw
     /// key-value pairs instead of keyHash->value pairs,
@@ -246,3 +346,111 @@ define_table_with_default_codec!(
     /// which requires the ability to fetch values by hash.
     (KeyHashToKey) [u8;32] => StateKey
 );
+
+define_table_without_codec!(
+    /// The JMT nodes which are not part of the tree anymore, by the version which replaced them.
+    /// Used to prune the nodes once that version falls out of the retention window.
+    (StaleNodes) StaleNodeIndex => ()
+);
+
+impl KeyEncoder<StaleNodes> for StaleNodeIndex {
+    fn encode_key(&self) -> sovereign_sdk::db::Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(std::mem::size_of::<Version>());
+        // Big-endian, so that the nodes are sorted by the version which made them stale
+        out.write_u64::<BigEndian>(self.stale_since_version)
+            .expect("serialization to vec is infallible");
+        out.extend(self.node_key.encode()?);
+        Ok(out)
+    }
+}
+
+impl KeyDecoder<StaleNodes> for StaleNodeIndex {
+    fn decode_key(data: &[u8]) -> sovereign_sdk::db::Result<Self> {
+        let mut cursor = maybestd::io::Cursor::new(data);
+        let stale_since_version = cursor.read_u64::<BigEndian>()?;
+        let node_key = NodeKey::decode(&data[cursor.position() as usize..])?;
+        Ok(Self {
+            stale_since_version,
+            node_key,
+        })
+    }
+}
+
+impl ValueCodec<StaleNodes> for () {
+    fn encode_value(&self) -> sovereign_sdk::db::Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+
+    fn decode_value(_data: &[u8]) -> sovereign_sdk::db::Result<Self> {
+        Ok(())
+    }
+}
+
+define_table_without_codec!(
+    /// The rows of `JmtValues` which were overwritten, by the version which overwrote them.
+    /// Deletions are stale as soon as they are written: once every readable version is past
+    /// a deletion, reading the key returns nothing with or without its row.
+    (StaleValues) (Version, StateKey, Version) => ()
+);
+
+impl KeyEncoder<StaleValues> for (Version, StateKey, Version) {
+    fn encode_key(&self) -> sovereign_sdk::db::Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(self.1.len() + 2 * std::mem::size_of::<Version>() + 8);
+        out.write_u64::<BigEndian>(self.0)
+            .expect("serialization to vec is infallible");
+        self.1.encode(&mut out);
+        out.write_u64::<BigEndian>(self.2)
+            .expect("serialization to vec is infallible");
+        Ok(out)
+    }
+}
+
+impl KeyDecoder<StaleValues> for (Version, StateKey, Version) {
+    fn decode_key(data: &[u8]) -> sovereign_sdk::db::Result<Self> {
+        let mut cursor = maybestd::io::Cursor::new(data);
+        let stale_since_version = cursor.read_u64::<BigEndian>()?;
+        let key = Vec::<u8>::decode(&mut cursor)?;
+        let version = cursor.read_u64::<BigEndian>()?;
+        Ok((stale_since_version, key, version))
+    }
+}
+
+impl ValueCodec<StaleValues> for () {
+    fn encode_value(&self) -> sovereign_sdk::db::Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+
+    fn decode_value(_data: &[u8]) -> sovereign_sdk::db::Result<Self> {
+        Ok(())
+    }
+}
+
+define_table_without_codec!(
+    /// The versions of the JMT which were fully written. Each version is marked in the same
+    /// write as its nodes and values, so the latest marker is the latest consistent version.
+    (CommittedVersions) Version => ()
+);
+
+impl KeyEncoder<CommittedVersions> for Version {
+    fn encode_key(&self) -> sovereign_sdk::db::Result<Vec<u8>> {
+        // Big-endian, so that the latest version is the last key
+        Ok(self.to_be_bytes().to_vec())
+    }
+}
+
+impl KeyDecoder<CommittedVersions> for Version {
+    fn decode_key(data: &[u8]) -> sovereign_sdk::db::Result<Self> {
+        let mut cursor = maybestd::io::Cursor::new(data);
+        Ok(cursor.read_u64::<BigEndian>()?)
+    }
+}
+
+impl ValueCodec<CommittedVersions> for () {
+    fn encode_value(&self) -> sovereign_sdk::db::Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+
+    fn decode_value(_data: &[u8]) -> sovereign_sdk::db::Result<Self> {
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use borsh::{BorshDeserialize, BorshSerialize};
-use sovereign_sdk::services::da::SlotData;
+use sovereign_sdk::{services::da::SlotData, stf::TxStatus};
 
 /// A cheaply cloneable bytes abstraction for use within the trust boundary of the node
 /// (i.e. when interfacing with the database). Serializes and deserializes more efficiently,
@@ -27,6 +27,16 @@ pub enum Status {
     Reverted,
 }
 
+impl From<TxStatus> for Status {
+    fn from(status: TxStatus) -> Self {
+        match status {
+            TxStatus::Applied => Status::Applied,
+            TxStatus::Skipped => Status::Skipped,
+            TxStatus::Reverted => Status::Reverted,
+        }
+    }
+}
+
 impl AsRef<[u8]> for DbBytes {
     fn as_ref(&self) -> &[u8] {
         self.0.as_ref()
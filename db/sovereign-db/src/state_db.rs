@@ -1,20 +1,25 @@
 use std::{
+    collections::HashMap,
     path::Path,
     sync::{Arc, Mutex},
 };
 
 use jmt::{
-    storage::{TreeReader, TreeWriter},
+    storage::{NodeBatch, TreeReader, TreeUpdateBatch, TreeWriter},
     KeyHash, Version,
 };
 
-use schemadb::DB;
+use schemadb::{SchemaBatch, DB};
 
 use crate::{
+    pruner::{PruningConfig, StatePruner},
     rocks_db_config::gen_rocksdb_options,
     schema::{
-        tables::{JmtNodes, JmtValues, KeyHashToKey, STATE_TABLES},
-        types::StateKey,
+        tables::{
+            CommittedVersions, JmtNodes, JmtValues, KeyHashToKey, StaleNodes, StaleValues,
+            StateKeys, STATE_TABLES,
+        },
+        types::{JmtValue, StateKey},
     },
 };
 
@@ -22,12 +27,29 @@ use crate::{
 pub struct StateDB {
     db: Arc<DB>,
     next_version: Arc<Mutex<Version>>,
+    pruner: Option<Arc<StatePruner>>,
 }
 
 const STATE_DB_PATH_SUFFIX: &'static str = "state";
 
 impl StateDB {
+    /// Opens a db which keeps all the versions.
     pub fn with_path(path: impl AsRef<Path>) -> Result<Self, anyhow::Error> {
+        Self::open(path, None)
+    }
+
+    /// Opens a db which deletes the versions older than the retention window in the background.
+    pub fn with_pruning(
+        path: impl AsRef<Path>,
+        config: PruningConfig,
+    ) -> Result<Self, anyhow::Error> {
+        Self::open(path, Some(config))
+    }
+
+    fn open(
+        path: impl AsRef<Path>,
+        pruning_config: Option<PruningConfig>,
+    ) -> Result<Self, anyhow::Error> {
         let path = path.as_ref().join(STATE_DB_PATH_SUFFIX);
         let inner = DB::open(
             path,
@@ -37,10 +59,20 @@ impl StateDB {
         )?;
 
         let next_version = Self::last_version_written(&inner)?.unwrap_or_default() + 1;
+        Self::index_state_keys(&inner)?;
+        let db = Arc::new(inner);
+        let pruner = pruning_config
+            .map(|config| StatePruner::start(db.clone(), config))
+            .transpose()?;
+        if let Some(pruner) = &pruner {
+            // Catch up with the versions committed before the restart
+            pruner.on_commit(next_version - 1);
+        }
 
         Ok(Self {
-            db: Arc::new(inner),
+            db,
             next_version: Arc::new(Mutex::new(next_version)),
+            pruner: pruner.map(Arc::new),
         })
     }
 
@@ -55,6 +87,7 @@ impl StateDB {
         self.db.put::<KeyHashToKey>(&key_hash.0, key)
     }
 
+    /// Returns the value of the key at `version`. Fails if the version is pruned.
     pub fn get_value_option_by_key(
         &self,
         version: Version,
@@ -64,6 +97,8 @@ impl StateDB {
         // find the latest instance of the key whose version <= target
         iter.seek_for_prev(&(&key, version))?;
         let found = iter.next();
+        // Checked after the read: if the version was readable then, the entries it needs weren't pruned yet.
+        self.ensure_readable(version)?;
         match found {
             Some(result) => {
                 let ((found_key, found_version), value) = result?;
@@ -78,21 +113,172 @@ impl StateDB {
         }
     }
 
-    pub fn update_db(
+    /// Returns the last key ordered before `key` which has a value at `version`, if any.
+    ///
+    /// The keys are found with a seek in the `StateKeys` index, then stepped back until one has a value at `version`.
+    pub fn get_key_before(&self, version: Version, key: &[u8]) -> anyhow::Result<Option<StateKey>> {
+        let mut iter = self.db.rev_iter::<StateKeys>()?;
+        iter.seek_for_prev(&key.to_vec())?;
+
+        let mut found = None;
+        for result in iter {
+            let (candidate, ()) = result?;
+            if candidate.as_slice() >= key {
+                continue;
+            }
+            // The index holds the keys of every version, the ones without a value at `version` are skipped
+            if self.get_value_option_by_key(version, &candidate)?.is_some() {
+                found = Some(candidate);
+                break;
+            }
+        }
+        // Checked even if no key was found, so that a pruned version never reads as empty
+        self.ensure_readable(version)?;
+        Ok(found)
+    }
+
+    // Writes a value without committing its version, so it isn't seen by the recovery on restart.
+    #[cfg(test)]
+    fn update_db(
         &self,
         key: StateKey,
         key_hash: KeyHash,
         value: Option<Vec<u8>>,
         next_version: Version,
     ) -> anyhow::Result<()> {
-        self.put_preimage(key_hash, &key)?;
-        self.db.put::<JmtValues>(&(key, next_version), &value)?;
+        let batch = SchemaBatch::new();
+        batch.put::<KeyHashToKey>(&key_hash.0, &key)?;
+        self.put_value(&batch, key, next_version, value)?;
+        self.db.write_schemas(batch)
+    }
+
+    /// Writes a version of the tree with a single atomic write: the preimages of the keys written by the version,
+    /// its nodes and values, the index of the nodes and values it made stale, and the marker of the committed version.
+    /// The next version is advanced only once the write succeeded.
+    ///
+    /// The stale entries are indexed only if the db is pruned: the versions committed without pruning are never pruned.
+    pub fn commit_version(
+        &self,
+        version: Version,
+        preimages: &HashMap<KeyHash, StateKey>,
+        tree_update: &TreeUpdateBatch,
+    ) -> anyhow::Result<()> {
+        let mut next_version = self.next_version.lock().unwrap();
+        anyhow::ensure!(
+            version == *next_version,
+            "Version {version} is not the next version {}",
+            *next_version
+        );
+
+        let batch = SchemaBatch::new();
+        for (key_hash, key) in preimages {
+            batch.put::<KeyHashToKey>(&key_hash.0, key)?;
+        }
+        self.put_node_batch(&batch, &tree_update.node_batch, preimages)?;
+        if self.pruner.is_some() {
+            for index in &tree_update.stale_node_index_batch {
+                batch.put::<StaleNodes>(index, &())?;
+            }
+        }
+        batch.put::<CommittedVersions>(&version, &())?;
+        self.db.write_schemas(batch)?;
+
+        *next_version += 1;
+        drop(next_version);
+        if let Some(pruner) = &self.pruner {
+            pruner.on_commit(version);
+        }
+        Ok(())
+    }
+
+    // The preimages of the keys which are new in the batch are looked up in `preimages`, the other ones in the db.
+    fn put_node_batch(
+        &self,
+        batch: &SchemaBatch,
+        node_batch: &NodeBatch,
+        preimages: &HashMap<KeyHash, StateKey>,
+    ) -> anyhow::Result<()> {
+        for (node_key, node) in node_batch.nodes() {
+            batch.put::<JmtNodes>(node_key, node)?;
+        }
+
+        for ((version, key_hash), value) in node_batch.values() {
+            let key_preimage = match preimages.get(key_hash) {
+                Some(key) => key.clone(),
+                None => self
+                    .db
+                    .get::<KeyHashToKey>(&key_hash.0)?
+                    .ok_or(anyhow::format_err!(
+                        "Could not find preimage for key hash {key_hash:?}"
+                    ))?,
+            };
+            self.put_value(batch, key_preimage, *version, value.clone())?;
+        }
+        Ok(())
+    }
+
+    // Writes a new version of the key, and marks the version it replaces as stale if the db is pruned.
+    fn put_value(
+        &self,
+        batch: &SchemaBatch,
+        key: StateKey,
+        version: Version,
+        value: JmtValue,
+    ) -> anyhow::Result<()> {
+        if self.pruner.is_some() {
+            if let Some(previous_version) = self.previous_version(&key, version)? {
+                batch.put::<StaleValues>(&(version, key.clone(), previous_version), &())?;
+            }
+            if value.is_none() {
+                batch.put::<StaleValues>(&(version, key.clone(), version), &())?;
+            }
+        }
+        batch.put::<StateKeys>(&key, &())?;
+        batch.put::<JmtValues>(&(key, version), &value)
+    }
+
+    // Returns the latest version of the key written before `version`.
+    fn previous_version(
+        &self,
+        key: &StateKey,
+        version: Version,
+    ) -> anyhow::Result<Option<Version>> {
+        if version == 0 {
+            return Ok(None);
+        }
+        let mut iter = self.db.iter::<JmtValues>()?;
+        iter.seek_for_prev(&(key, version - 1))?;
+        match iter.next() {
+            Some(result) => {
+                let ((found_key, found_version), _) = result?;
+                Ok((&found_key == key).then_some(found_version))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn ensure_readable(&self, version: Version) -> anyhow::Result<()> {
+        let oldest_version = self.oldest_version();
+        anyhow::ensure!(
+            version >= oldest_version,
+            "Version {version} is pruned, the oldest version is {oldest_version}"
+        );
         Ok(())
     }
 
-    pub fn inc_next_version(&self) {
-        let mut version = self.next_version.lock().unwrap();
-        *version += 1;
+    /// Returns the oldest version which can be read. Older versions are pruned, or about to be.
+    pub fn oldest_version(&self) -> Version {
+        match &self.pruner {
+            Some(pruner) => pruner.oldest_version(self.get_next_version() - 1),
+            None => 0,
+        }
+    }
+
+    /// Blocks until the versions which fell out of the retention window are pruned.
+    pub fn wait_for_pruning(&self) {
+        if let Some(pruner) = &self.pruner {
+            pruner.wait();
+        }
     }
 
     pub fn get_next_version(&self) -> Version {
@@ -100,15 +286,51 @@ impl StateDB {
         *version
     }
 
+    // The data of a version is written together with its marker, so the data of a version
+    // without a marker was never written.
     fn last_version_written(db: &DB) -> anyhow::Result<Option<Version>> {
-        let mut iter = db.iter::<JmtValues>()?;
+        let mut iter = db.iter::<CommittedVersions>()?;
         iter.seek_to_last();
 
-        let version = match iter.next() {
-            Some(Ok(((_, version), _))) => Some(version),
-            _ => None,
-        };
-        Ok(version)
+        match iter.next() {
+            Some(result) => Ok(Some(result?.0)),
+            None => Self::migrate_committed_versions(db),
+        }
+    }
+
+    // The dbs written before the `StateKeys` index have values but no index, it is built from the values.
+    fn index_state_keys(db: &DB) -> anyhow::Result<()> {
+        let mut index = db.iter::<StateKeys>()?;
+        index.seek_to_first();
+        if index.next().is_some() {
+            return Ok(());
+        }
+
+        let mut values = db.iter::<JmtValues>()?;
+        values.seek_to_first();
+        let batch = SchemaBatch::new();
+        for result in values {
+            let ((key, _), _) = result?;
+            batch.put::<StateKeys>(&key, &())?;
+        }
+        db.write_schemas(batch)
+    }
+
+    // The dbs written before the markers were introduced have values but no marker. Their latest
+    // version is the latest version of any value, which is marked so that the scan runs only once.
+    fn migrate_committed_versions(db: &DB) -> anyhow::Result<Option<Version>> {
+        let mut iter = db.iter::<JmtValues>()?;
+        iter.seek_to_first();
+
+        let mut last_version = None;
+        for result in iter {
+            let ((_, version), _) = result?;
+            last_version = last_version.max(Some(version));
+        }
+        if let Some(version) = last_version {
+            db.put::<CommittedVersions>(&version, &())?;
+        }
+        Ok(last_version)
     }
 }
 
@@ -140,32 +362,42 @@ impl TreeReader for StateDB {
 }
 
 impl TreeWriter for StateDB {
-    fn write_node_batch(&self, node_batch: &jmt::storage::NodeBatch) -> anyhow::Result<()> {
-        for (node_key, node) in node_batch.nodes() {
-            self.db.put::<JmtNodes>(node_key, node)?;
-        }
-
-        for ((version, key_hash), value) in node_batch.values() {
-            let key_preimage =
-                self.db
-                    .get::<KeyHashToKey>(&key_hash.0)?
-                    .ok_or(anyhow::format_err!(
-                        "Could not find preimage for key hash {key_hash:?}"
-                    ))?;
-            self.db.put::<JmtValues>(&(key_preimage, *version), value)?;
-        }
-        Ok(())
+    /// Commits the nodes and values as the next version, see [`StateDB::commit_version`].
+    /// Fails if the batch writes any other version, so that no version is written without its marker.
+    fn write_node_batch(&self, node_batch: &NodeBatch) -> anyhow::Result<()> {
+        let version = self.get_next_version();
+        anyhow::ensure!(
+            node_batch
+                .nodes()
+                .keys()
+                .all(|node_key| node_key.version() == version)
+                && node_batch.values().keys().all(|(v, _)| *v == version),
+            "The node batch must only write the next version {version}"
+        );
+        let tree_update = TreeUpdateBatch {
+            node_batch: node_batch.clone(),
+            ..Default::default()
+        };
+        self.commit_version(version, &HashMap::new(), &tree_update)
     }
 }
 
 #[cfg(test)]
 mod state_db_tests {
+    use std::collections::HashMap;
+
     use jmt::{
-        storage::{NodeBatch, TreeReader, TreeWriter},
+        storage::{
+            Node, NodeBatch, NodeKey, StaleNodeIndex, TreeReader, TreeUpdateBatch, TreeWriter,
+        },
         KeyHash,
     };
 
     use super::StateDB;
+    use crate::{
+        pruner::PruningConfig,
+        schema::tables::{CommittedVersions, JmtValues, StateKeys},
+    };
 
     #[test]
     fn test_simple() {
@@ -176,13 +408,221 @@ mod state_db_tests {
 
         db.put_preimage(key_hash, &key).unwrap();
         let mut batch = NodeBatch::default();
-        batch.extend(vec![], vec![((0, key_hash), Some(value.to_vec()))]);
+        batch.extend(vec![], vec![((1, key_hash), Some(value.to_vec()))]);
         db.write_node_batch(&batch).unwrap();
 
-        let found = db.get_value(0, key_hash).unwrap();
+        let found = db.get_value(1, key_hash).unwrap();
         assert_eq!(found, value);
 
-        let found = db.get_value_option_by_key(0, &key).unwrap().unwrap();
+        let found = db.get_value_option_by_key(1, &key).unwrap().unwrap();
         assert_eq!(found, value);
     }
+
+    #[test]
+    fn test_write_node_batch_commits_version() {
+        let path = schemadb::temppath::TempPath::new();
+        let key_hash = KeyHash([1u8; 32]);
+        let key = vec![2u8; 10];
+        let node_batch = |version| {
+            let mut batch = NodeBatch::default();
+            batch.extend(
+                vec![],
+                vec![((version, key_hash), Some(vec![version as u8]))],
+            );
+            batch
+        };
+
+        {
+            let db = StateDB::with_path(&path).unwrap();
+            db.put_preimage(key_hash, &key).unwrap();
+            db.write_node_batch(&node_batch(1)).unwrap();
+            // A batch of another version is rejected, and nothing is written.
+            assert!(db.write_node_batch(&node_batch(3)).is_err());
+            assert_eq!(
+                db.get_value_option_by_key(3, &key).unwrap(),
+                Some(vec![1u8])
+            );
+            assert_eq!(db.get_next_version(), 2);
+        }
+
+        // The written version is recovered on restart.
+        let db = StateDB::with_path(&path).unwrap();
+        assert_eq!(db.get_next_version(), 2);
+        assert_eq!(db.get_value(1, key_hash).unwrap(), vec![1u8]);
+        assert_eq!(
+            db.get_value_option_by_key(3, &key).unwrap(),
+            Some(vec![1u8])
+        );
+    }
+
+    #[test]
+    fn test_get_key_before() {
+        let db = StateDB::temporary();
+        let entries = vec![
+            (vec![1u8, 1], vec![1u8], 0),
+            (vec![1u8, 1], vec![2u8], 1),
+            (vec![1u8, 2, 2], vec![3u8], 0),
+            (vec![1u8, 3], vec![4u8], 2),
+            (vec![2u8, 1], vec![5u8], 0),
+        ];
+
+        for (i, (key, value, version)) in entries.into_iter().enumerate() {
+            db.update_db(key, KeyHash([i as u8; 32]), Some(value), version)
+                .unwrap();
+        }
+        // Deleted keys are skipped
+        db.update_db(vec![1u8, 2, 2], KeyHash([2u8; 32]), None, 2)
+            .unwrap();
+
+        assert_eq!(db.get_key_before(0, &[2]).unwrap(), Some(vec![1u8, 2, 2]));
+        assert_eq!(
+            db.get_key_before(1, &[1, 3]).unwrap(),
+            Some(vec![1u8, 2, 2])
+        );
+        assert_eq!(db.get_key_before(2, &[1, 3]).unwrap(), Some(vec![1u8, 1]));
+        assert_eq!(db.get_key_before(2, &[2, 1]).unwrap(), Some(vec![1u8, 3]));
+        assert_eq!(db.get_key_before(2, &[1, 1]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_pruning() {
+        let path = schemadb::temppath::TempPath::new();
+        let db = StateDB::with_pruning(
+            &path,
+            PruningConfig {
+                retention_window: 1,
+            },
+        )
+        .unwrap();
+        let (key_a, key_b) = (vec![1u8], vec![2u8]);
+        let (hash_a, hash_b) = (KeyHash([1u8; 32]), KeyHash([2u8; 32]));
+
+        let updates = vec![
+            vec![
+                (&key_a, hash_a, Some(vec![1u8])),
+                (&key_b, hash_b, Some(vec![2u8])),
+            ],
+            vec![(&key_a, hash_a, Some(vec![3u8])), (&key_b, hash_b, None)],
+            vec![(&key_a, hash_a, Some(vec![4u8]))],
+        ];
+        for (version, values) in (1..).zip(updates) {
+            let mut preimages = HashMap::new();
+            let mut tree_update = TreeUpdateBatch::default();
+            let mut node_values = Vec::new();
+            for (key, key_hash, value) in values {
+                preimages.insert(key_hash, key.clone());
+                node_values.push(((version, key_hash), value));
+            }
+            // Every version replaces the root node of the previous one
+            tree_update.node_batch.extend(
+                vec![(NodeKey::new_empty_path(version), Node::Null)],
+                node_values,
+            );
+            tree_update.stale_node_index_batch.insert(StaleNodeIndex {
+                stale_since_version: version,
+                node_key: NodeKey::new_empty_path(version - 1),
+            });
+            db.commit_version(version, &preimages, &tree_update)
+                .unwrap();
+        }
+        db.wait_for_pruning();
+        assert_eq!(db.oldest_version(), 3);
+
+        // Only the state of the latest version is left
+        assert_eq!(
+            db.get_value_option_by_key(3, &key_a).unwrap(),
+            Some(vec![4u8])
+        );
+        // Reading a pruned version fails, rather than returning a partial state
+        assert!(db.get_value_option_by_key(2, &key_a).is_err());
+        assert!(db.get_value_option_by_key(1, &key_b).is_err());
+        assert!(db.get_key_before(2, &[3]).is_err());
+        // The stale entries themselves are deleted
+        for (key, version) in [(&key_a, 1), (&key_a, 2), (&key_b, 1), (&key_b, 2)] {
+            assert!(db
+                .db
+                .get::<JmtValues>(&(key.clone(), version))
+                .unwrap()
+                .is_none());
+        }
+        for version in 1..3 {
+            assert!(db
+                .get_node_option(&NodeKey::new_empty_path(version))
+                .unwrap()
+                .is_none());
+        }
+        assert!(db
+            .get_node_option(&NodeKey::new_empty_path(3))
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_commit_version() {
+        let path = schemadb::temppath::TempPath::new();
+        let key_hash = KeyHash([1u8; 32]);
+        let key = vec![2u8; 10];
+        let preimages = HashMap::from([(key_hash, key.clone())]);
+        let mut tree_update = TreeUpdateBatch::default();
+        tree_update
+            .node_batch
+            .extend(vec![], vec![((1, key_hash), Some(vec![3u8]))]);
+
+        {
+            let db = StateDB::with_path(&path).unwrap();
+            assert_eq!(db.get_next_version(), 1);
+            // Only the next version can be committed
+            assert!(db.commit_version(2, &preimages, &tree_update).is_err());
+            assert_eq!(db.get_value_option_by_key(2, &key).unwrap(), None);
+
+            db.commit_version(1, &preimages, &tree_update).unwrap();
+            // A version which doesn't write any value
+            db.commit_version(2, &HashMap::new(), &TreeUpdateBatch::default())
+                .unwrap();
+            assert_eq!(db.get_next_version(), 3);
+        }
+
+        // The written versions are recovered on restart.
+        let db = StateDB::with_path(&path).unwrap();
+        assert_eq!(db.get_next_version(), 3);
+        assert_eq!(db.get_value(2, key_hash).unwrap(), vec![3u8]);
+    }
+
+    #[test]
+    fn test_recover_version_without_markers() {
+        let path = schemadb::temppath::TempPath::new();
+        let key_hash = KeyHash([1u8; 32]);
+        {
+            // A db written before the markers: values without any committed version.
+            let db = StateDB::with_path(&path).unwrap();
+            db.update_db(vec![1u8], key_hash, Some(vec![1u8]), 4)
+                .unwrap();
+            db.update_db(vec![2u8], KeyHash([2u8; 32]), Some(vec![2u8]), 3)
+                .unwrap();
+        }
+
+        // The latest version is recovered from the values, and marked.
+        let db = StateDB::with_path(&path).unwrap();
+        assert_eq!(db.get_next_version(), 5);
+        assert_eq!(db.db.get::<CommittedVersions>(&4).unwrap(), Some(()));
+        assert_eq!(db.get_value(4, key_hash).unwrap(), vec![1u8]);
+    }
+
+    #[test]
+    fn test_index_state_keys_on_open() {
+        let path = schemadb::temppath::TempPath::new();
+        {
+            let db = StateDB::with_path(&path).unwrap();
+            db.update_db(vec![1u8, 1], KeyHash([1u8; 32]), Some(vec![1u8]), 1)
+                .unwrap();
+            // A db written before the index
+            let batch = schemadb::SchemaBatch::new();
+            batch.delete::<StateKeys>(&vec![1u8, 1]).unwrap();
+            db.db.write_schemas(batch).unwrap();
+            assert_eq!(db.get_key_before(1, &[2]).unwrap(), None);
+        }
+
+        let db = StateDB::with_path(&path).unwrap();
+        assert_eq!(db.get_key_before(1, &[2]).unwrap(), Some(vec![1u8, 1]));
+    }
 }
@@ -6,6 +6,9 @@ use sov_app_template::RawTx;
 use sov_modules_api::mocks::{MockContext, MockPublicKey, MockSignature};
 use sov_modules_api::PublicKey;
 
+/// Enough gas for any of the generated calls.
+pub(crate) const DEFAULT_GAS_LIMIT: u64 = 100_000;
+
 pub(crate) fn simulate_da() -> Vec<RawTx> {
     let mut messages = Vec::default();
     messages.extend(CallGenerator::election_call_messages());
@@ -57,6 +60,7 @@ impl CallGenerator {
                     sender,
                     MockSignature::default(),
                     nonce,
+                    DEFAULT_GAS_LIMIT,
                 )
                 .try_to_vec()
                 .unwrap(),
@@ -82,6 +86,7 @@ impl CallGenerator {
                     admin.clone(),
                     MockSignature::default(),
                     0,
+                    DEFAULT_GAS_LIMIT,
                 )
                 .try_to_vec()
                 .unwrap(),
@@ -92,6 +97,7 @@ impl CallGenerator {
                     admin,
                     MockSignature::default(),
                     1,
+                    DEFAULT_GAS_LIMIT,
                 )
                 .try_to_vec()
                 .unwrap(),
@@ -0,0 +1,46 @@
+use std::path::Path;
+
+use anyhow::Context as _;
+use sov_modules_api::mocks::{MockContext, MockPublicKey};
+use sov_modules_api::PublicKey;
+
+use crate::runtime::RuntimeGenesisConfig;
+
+/// The genesis configuration used by the demo: the admins are the senders of the transactions in `simulate_da`.
+pub(crate) fn create_demo_genesis_config() -> RuntimeGenesisConfig<MockContext> {
+    let election_admin = MockPublicKey::try_from("election_admin").unwrap();
+    let value_setter_admin = MockPublicKey::try_from("value_setter_admin").unwrap();
+
+    RuntimeGenesisConfig {
+        election: election::genesis::ElectionConfig {
+            admin: election_admin.to_address(),
+        },
+        value_setter: value_setter::ValueSetterConfig {
+            admin: value_setter_admin.to_address(),
+        },
+        accounts: accounts::AccountConfig {
+            pub_keys: vec![election_admin, value_setter_admin],
+        },
+        bank: bank::BankConfig {
+            tokens: vec![bank::TokenConfig {
+                token_name: "sov-demo-token".to_owned(),
+                address_and_balances: vec![(
+                    MockPublicKey::try_from("token_holder")
+                        .unwrap()
+                        .to_address(),
+                    1000,
+                )],
+            }],
+        },
+    }
+}
+
+/// Reads the genesis configuration from a JSON file.
+pub(crate) fn read_genesis_config(
+    path: impl AsRef<Path>,
+) -> anyhow::Result<RuntimeGenesisConfig<MockContext>> {
+    let path = path.as_ref();
+    let config = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read the genesis config from {}", path.display()))?;
+    Ok(serde_json::from_str(&config)?)
+}
@@ -1,4 +1,5 @@
 mod data_generation;
+mod genesis_config;
 mod helpers;
 mod runtime;
 
@@ -8,11 +9,12 @@ mod tx_verifier_impl;
 use std::path::Path;
 
 use data_generation::{simulate_da, QueryGenerator};
+use genesis_config::{create_demo_genesis_config, read_genesis_config};
 use helpers::check_query;
 use runtime::Runtime;
 use sov_modules_api::mocks::MockContext;
 use sov_state::ProverStorage;
-use sovereign_sdk::stf::StateTransitionFunction;
+use sovereign_sdk::stf::{SlotInfo, StateTransitionFunction};
 
 use sov_app_template::{AppTemplate, Batch};
 use tx_hooks_impl::DemoAppTxHooks;
@@ -30,12 +32,27 @@ fn create_new_demo(path: impl AsRef<Path>) -> DemoApp {
     AppTemplate::new(storage, runtime, tx_verifier, tx_hooks)
 }
 
+/// Describes the DA layer block at `height`, the demo doesn't read the blocks from a real DA layer.
+fn mock_slot(height: u64) -> SlotInfo {
+    SlotInfo {
+        da_height: height,
+        da_hash: [height as u8; 32],
+        timestamp: height * 10,
+    }
+}
+
 fn main() {
+    // The genesis config can be passed as a JSON file, the demo config is used otherwise.
+    let genesis_config = match std::env::args().nth(1) {
+        Some(config_path) => read_genesis_config(config_path).expect("Invalid genesis config"),
+        None => create_demo_genesis_config(),
+    };
+
     let path = schemadb::temppath::TempPath::new();
     {
         let mut demo = create_new_demo(&path);
-        demo.init_chain(());
-        demo.begin_slot();
+        demo.init_chain(genesis_config);
+        demo.begin_slot(mock_slot(1));
 
         let txs = simulate_da();
 
@@ -69,14 +86,51 @@ fn main() {
 mod test {
 
     use super::*;
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use data_generation::DEFAULT_GAS_LIMIT;
+    use sov_app_template::RawTx;
+    use sov_modules_api::mocks::{MockPublicKey, MockSignature};
+    use sovereign_sdk::stf::{TxError, TxStatus};
+    use tx_verifier_impl::Transaction;
+
+    fn set_value_tx(sender: &'static str, new_value: u32, nonce: u64) -> RawTx {
+        set_value_tx_with_gas(sender, new_value, nonce, DEFAULT_GAS_LIMIT)
+    }
+
+    fn set_value_tx_with_gas(
+        sender: &'static str,
+        new_value: u32,
+        nonce: u64,
+        gas_limit: u64,
+    ) -> RawTx {
+        let set_value_msg =
+            value_setter::call::CallMessage::DoSetValue(value_setter::call::SetValue { new_value });
+
+        RawTx {
+            data: Transaction::<C>::new(
+                Runtime::<C>::encode_value_setter_call(set_value_msg),
+                MockPublicKey::try_from(sender).unwrap(),
+                MockSignature::default(),
+                nonce,
+                gas_limit,
+            )
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    fn bad_nonce_tx() -> RawTx {
+        set_value_tx("value_setter_admin", 77, 100)
+    }
+
     #[test]
     fn test_demo_values_in_db() {
         let path = schemadb::temppath::TempPath::new();
         {
             let mut demo = create_new_demo(&path);
 
-            demo.init_chain(());
-            demo.begin_slot();
+            demo.init_chain(create_demo_genesis_config());
+            demo.begin_slot(mock_slot(1));
 
             let txs = simulate_da();
 
@@ -106,13 +160,74 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_query_at_past_version() {
+        let path = schemadb::temppath::TempPath::new();
+        let mut demo = create_new_demo(&path);
+
+        demo.init_chain(create_demo_genesis_config());
+        demo.begin_slot(mock_slot(1));
+        demo.apply_batch(Batch { txs: simulate_da() }, &[1u8; 32], None)
+            .expect("Batch is valid");
+        demo.end_slot();
+
+        demo.begin_slot(mock_slot(2));
+        let txs = vec![set_value_tx("value_setter_admin", 13, 2)];
+        demo.apply_batch(Batch { txs }, &[1u8; 32], None)
+            .expect("Batch is valid");
+        demo.end_slot();
+
+        // The genesis and each slot commit a version.
+        let storage = demo.current_storage;
+        assert_eq!(storage.latest_version(), 3);
+        let runtime = &mut Runtime::<C>::new();
+        for (version, expected_response) in [
+            (1, r#"{"value":null}"#),
+            (2, r#"{"value":33}"#),
+            (3, r#"{"value":13}"#),
+        ] {
+            check_query(
+                runtime,
+                QueryGenerator::generate_query_value_setter_message(),
+                expected_response,
+                storage.at_version(version).unwrap(),
+            );
+        }
+    }
+
+    #[test]
+    fn test_genesis_config_from_json() {
+        let config_path = schemadb::temppath::TempPath::new();
+        std::fs::write(
+            &config_path,
+            serde_json::to_string(&create_demo_genesis_config()).unwrap(),
+        )
+        .unwrap();
+
+        let path = schemadb::temppath::TempPath::new();
+        let mut demo = create_new_demo(&path);
+        demo.init_chain(read_genesis_config(&config_path).unwrap());
+        demo.begin_slot(mock_slot(1));
+        demo.apply_batch(Batch { txs: simulate_da() }, &[1u8; 32], None)
+            .expect("Batch is valid");
+        demo.end_slot();
+
+        let runtime = &mut Runtime::<C>::new();
+        check_query(
+            runtime,
+            QueryGenerator::generate_query_value_setter_message(),
+            r#"{"value":33}"#,
+            demo.current_storage,
+        );
+    }
+
     #[test]
     fn test_demo_values_in_cache() {
         let path = schemadb::temppath::TempPath::new();
         let mut demo = create_new_demo(&path);
 
-        demo.init_chain(());
-        demo.begin_slot();
+        demo.init_chain(create_demo_genesis_config());
+        demo.begin_slot(mock_slot(1));
 
         let txs = simulate_da();
 
@@ -142,8 +257,8 @@ mod test {
         {
             let mut demo = create_new_demo(&path);
 
-            demo.init_chain(());
-            demo.begin_slot();
+            demo.init_chain(create_demo_genesis_config());
+            demo.begin_slot(mock_slot(1));
 
             let txs = simulate_da();
 
@@ -170,4 +285,275 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn test_end_slot_after_failed_tx() {
+        let path = schemadb::temppath::TempPath::new();
+        {
+            let mut demo = create_new_demo(&path);
+
+            demo.init_chain(create_demo_genesis_config());
+            demo.begin_slot(mock_slot(1));
+            demo.apply_batch(Batch { txs: simulate_da() }, &[1u8; 32], None)
+                .expect("Batch is valid");
+            let (first_root, _) = demo.end_slot();
+
+            // Only the admin can set the value, so the call fails in the module.
+            demo.begin_slot(mock_slot(2));
+            let receipt = demo
+                .apply_batch(
+                    Batch {
+                        txs: vec![set_value_tx("non_admin", 77, 0)],
+                    },
+                    &[1u8; 32],
+                    None,
+                )
+                .expect("Batch is valid");
+            assert_eq!(receipt.tx_receipts[0].status, TxStatus::Reverted);
+
+            // The slot is committed: the nonce bump of the failed tx changes the root.
+            let (second_root, _) = demo.end_slot();
+            assert_ne!(first_root.0, second_root.0);
+        }
+
+        // The failed tx must not change the value set in the first slot.
+        {
+            let runtime = &mut Runtime::<C>::new();
+            let storage = ProverStorage::with_path(&path).unwrap();
+            check_query(
+                runtime,
+                QueryGenerator::generate_query_value_setter_message(),
+                r#"{"value":33}"#,
+                storage,
+            );
+        }
+    }
+
+    #[test]
+    fn test_stale_nonce_tx_slashes_sequencer() {
+        let path = schemadb::temppath::TempPath::new();
+        {
+            let mut demo = create_new_demo(&path);
+
+            demo.init_chain(create_demo_genesis_config());
+            demo.begin_slot(mock_slot(1));
+            demo.apply_batch(Batch { txs: simulate_da() }, &[1u8; 32], None)
+                .expect("Batch is valid");
+            demo.end_slot();
+
+            // A tx rejected by the pre-dispatch hook invalidates its whole batch.
+            demo.begin_slot(mock_slot(2));
+            let txs = vec![set_value_tx("value_setter_admin", 13, 2), bad_nonce_tx()];
+            demo.apply_batch(Batch { txs }, &[1u8; 32], None)
+                .expect_err("Tx with a bad nonce must be rejected");
+            demo.end_slot();
+        }
+
+        // The batch is reverted, including the tx applied before the rejected one.
+        {
+            let runtime = &mut Runtime::<C>::new();
+            let storage = ProverStorage::with_path(&path).unwrap();
+            check_query(
+                runtime,
+                QueryGenerator::generate_query_value_setter_message(),
+                r#"{"value":33}"#,
+                storage,
+            );
+        }
+    }
+
+    #[test]
+    fn test_failed_call_is_reverted() {
+        let path = schemadb::temppath::TempPath::new();
+        {
+            let mut demo = create_new_demo(&path);
+
+            demo.init_chain(create_demo_genesis_config());
+            demo.begin_slot(mock_slot(1));
+
+            // Only the admin can set the value, so the calls of `non_admin` fail.
+            let txs = vec![
+                set_value_tx("non_admin", 11, 0),
+                // Passes the nonce check only if the nonce bump of the failed tx was kept.
+                set_value_tx("non_admin", 12, 1),
+                set_value_tx("value_setter_admin", 13, 0),
+            ];
+
+            let receipt = demo
+                .apply_batch(Batch { txs }, &[1u8; 32], None)
+                .expect("Batch is valid");
+            demo.end_slot();
+
+            let receipts = receipt.tx_receipts;
+            assert_eq!(receipts.len(), 3);
+            for tx_receipt in &receipts[..2] {
+                assert_eq!(tx_receipt.status, TxStatus::Reverted);
+                assert!(tx_receipt.events.is_empty());
+                assert_eq!(
+                    tx_receipt.error,
+                    Some(TxError {
+                        module: Some("value_setter".to_owned()),
+                        code: value_setter::call::SetValueError::WrongSender.code(),
+                        message: "Only admin can change the value".to_owned(),
+                    })
+                );
+            }
+            assert_eq!(receipts[2].status, TxStatus::Applied);
+            let events = &receipts[2].events;
+            assert_eq!(events.len(), 1);
+            assert_eq!(
+                value_setter::call::ValueSetterEvent::try_from_slice(events[0].value.as_ref())
+                    .unwrap(),
+                value_setter::call::ValueSetterEvent::ValueSet { new_value: 13 }
+            );
+            // The event is attributed to the module which emitted it.
+            let module = events[0].module.as_ref().unwrap();
+            let value_setter = Runtime::<C>::new()
+                .modules()
+                .into_iter()
+                .find(|descriptor| descriptor.name == "value_setter")
+                .unwrap();
+            assert_eq!(module.name, value_setter.name);
+            assert_eq!(module.address, value_setter.address.as_ref());
+        }
+
+        {
+            let runtime = &mut Runtime::<C>::new();
+            let storage = ProverStorage::with_path(&path).unwrap();
+            check_query(
+                runtime,
+                QueryGenerator::generate_query_value_setter_message(),
+                r#"{"value":13}"#,
+                storage,
+            );
+        }
+    }
+
+    #[test]
+    fn test_out_of_gas_tx_is_reverted() {
+        let path = schemadb::temppath::TempPath::new();
+        {
+            let mut demo = create_new_demo(&path);
+
+            demo.init_chain(create_demo_genesis_config());
+            demo.begin_slot(mock_slot(1));
+
+            let txs = vec![
+                set_value_tx("value_setter_admin", 11, 0),
+                set_value_tx_with_gas("value_setter_admin", 12, 1, 1),
+                // The nonce was bumped by the post-dispatch hook of the out of gas tx.
+                set_value_tx("value_setter_admin", 13, 2),
+            ];
+            let receipt = demo
+                .apply_batch(Batch { txs }, &[1u8; 32], None)
+                .expect("Batch is valid");
+            demo.end_slot();
+
+            let receipts = receipt.tx_receipts;
+            assert_eq!(receipts[0].status, TxStatus::Applied);
+            assert!(receipts[0].gas_used > 0);
+            assert!(receipts[0].gas_used <= DEFAULT_GAS_LIMIT);
+
+            assert_eq!(receipts[1].status, TxStatus::Reverted);
+            assert!(receipts[1].events.is_empty());
+            assert_eq!(receipts[1].gas_used, 1);
+            let error = receipts[1].error.as_ref().unwrap();
+            assert_eq!(error.module, None);
+            assert!(error.message.starts_with("Out of gas"));
+            assert_eq!(receipts[2].status, TxStatus::Applied);
+        }
+
+        {
+            let runtime = &mut Runtime::<C>::new();
+            let storage = ProverStorage::with_path(&path).unwrap();
+            check_query(
+                runtime,
+                QueryGenerator::generate_query_value_setter_message(),
+                r#"{"value":13}"#,
+                storage,
+            );
+        }
+    }
+
+    #[test]
+    fn test_out_of_gas_tx_stops_its_work() {
+        let set_candidates_tx = |count: usize, nonce: u64, gas_limit: u64| {
+            let names = (0..count).map(|i| format!("candidate_{i}")).collect();
+            RawTx {
+                data: Transaction::<C>::new(
+                    Runtime::<C>::encode_election_call(
+                        election::call::CallMessage::SetCandidates { names },
+                    ),
+                    MockPublicKey::try_from("election_admin").unwrap(),
+                    MockSignature::default(),
+                    nonce,
+                    gas_limit,
+                )
+                .try_to_vec()
+                .unwrap(),
+            }
+        };
+
+        let path = schemadb::temppath::TempPath::new();
+        let mut demo = create_new_demo(&path);
+        demo.init_chain(create_demo_genesis_config());
+        demo.begin_slot(mock_slot(1));
+
+        let gas_limit = 5_000;
+        let txs = vec![
+            // Would write ten thousand candidates with enough gas.
+            set_candidates_tx(10_000, 0, gas_limit),
+            set_candidates_tx(2, 1, DEFAULT_GAS_LIMIT),
+        ];
+        let receipt = demo
+            .apply_batch(Batch { txs }, &[1u8; 32], None)
+            .expect("Batch is valid");
+        demo.end_slot();
+
+        let receipts = receipt.tx_receipts;
+        assert_eq!(receipts[0].status, TxStatus::Reverted);
+        assert_eq!(receipts[0].gas_used, gas_limit);
+        assert!(receipts[0]
+            .error
+            .as_ref()
+            .unwrap()
+            .message
+            .starts_with("Out of gas"));
+        // The candidates of the out of gas tx were not kept.
+        assert_eq!(receipts[1].status, TxStatus::Applied);
+    }
+
+    #[test]
+    fn test_duplicate_tx_is_skipped() {
+        let path = schemadb::temppath::TempPath::new();
+        let mut demo = create_new_demo(&path);
+
+        demo.init_chain(create_demo_genesis_config());
+        demo.begin_slot(mock_slot(1));
+
+        let tx = set_value_tx("value_setter_admin", 13, 0);
+        let batch = Batch {
+            txs: vec![tx.clone(), tx.clone()],
+        };
+        let batch_hash = batch.hash::<C>();
+
+        let receipt = demo
+            .apply_batch(batch, &[1u8; 32], None)
+            .expect("Batch is valid");
+
+        assert_eq!(receipt.batch_hash, batch_hash);
+        assert_eq!(receipt.tx_receipts.len(), 2);
+        for tx_receipt in &receipt.tx_receipts {
+            assert_eq!(tx_receipt.tx_hash, tx.hash::<C>());
+            assert_eq!(tx_receipt.body, tx.data);
+        }
+        assert_eq!(receipt.tx_receipts[0].status, TxStatus::Applied);
+        assert_eq!(receipt.tx_receipts[1].status, TxStatus::Skipped);
+
+        // The duplicate is detected across the batches of a slot.
+        let receipt = demo
+            .apply_batch(Batch { txs: vec![tx] }, &[1u8; 32], None)
+            .expect("Batch is valid");
+        assert_eq!(receipt.tx_receipts[0].status, TxStatus::Skipped);
+    }
 }
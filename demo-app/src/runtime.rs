@@ -1,5 +1,7 @@
-use sov_modules_api::{Context, Module, ModuleInfo};
-use sov_modules_macros::{DispatchCall, DispatchQuery, Genesis, MessageCodec};
+use sov_modules_api::{Context, Module};
+use sov_modules_macros::{
+    DefaultRuntime, DispatchCall, DispatchQuery, Genesis, MessageCodec, SlotHooks,
+};
 
 /// On a high level, the rollup node receives serialized call messages from the DA layer and executes them as atomic transactions.
 /// Upon reception, the message has to be deserialized and forwarded to an appropriate module.
@@ -20,7 +22,8 @@ use sov_modules_macros::{DispatchCall, DispatchQuery, Genesis, MessageCodec};
 /// 1. Initialization:
 ///     When a rollup is deployed for the first time, it needs to set its genesis state.
 ///     The `#[derive(Genesis)` macro will generate `Runtime::genesis(config)` method which returns
-///     `Storage` with the initialized state.
+///     `Storage` with the initialized state. The `config` is a `RuntimeGenesisConfig` struct (generated by the same macro)
+///     composed of the configurations of all the modules, which can be loaded from a JSON file.
 ///
 /// 2. Calls:      
 ///     The `Module` interface defines a `call` method which accepts a module-defined type and triggers the specific `module logic.`
@@ -30,14 +33,22 @@ use sov_modules_macros::{DispatchCall, DispatchQuery, Genesis, MessageCodec};
 /// 3. Queries:
 ///    The `Module` interface defines a `query` method, which allows querying the state of the module.
 ///     Queries are read only i.e they don't change the state of the rollup.
+///
+/// 4. Slot hooks:
+///     The `Module` interface defines `begin_slot_hook` and `end_slot_hook` methods, which are called at the beginning
+///     and at the end of each DA layer block. `#[derive(SlotHooks)]` calls the hooks of all the modules, in the order of the fields.
 ///     
+/// `#[derive(DefaultRuntime)` generates the `Runtime::new` constructor, which creates every module, and the
+/// `Runtime::modules` method listing the name, address and prefix of each module. Adding a module to the rollup
+/// only requires adding a field to the `Runtime` struct.
+///
 /// `#[derive(MessageCodec)` adds deserialization capabilities to the `Runtime` (implements `decode_call` method).
 /// `Runtime::decode_call` accepts serialized call message and returns a type that implements the `DispatchCall` trait.
 ///  The `DispatchCall` implementation (derived by a macro) forwards the message to the appropriate module and executes its `call` method.
 ///
 /// Similar mechanism works for queries with the difference that queries are submitted by users directly to the rollup node
 /// instead of going through the DA layer.
-#[derive(Genesis, DispatchCall, DispatchQuery, MessageCodec)]
+#[derive(DefaultRuntime, Genesis, DispatchCall, DispatchQuery, MessageCodec, SlotHooks)]
 pub(crate) struct Runtime<C: Context> {
     /// Definition of the first module in the rollup (must implement the sov_modules_api::Module trait).
     #[allow(unused)]
@@ -48,15 +59,7 @@ pub(crate) struct Runtime<C: Context> {
 
     #[allow(unused)]
     accounts: accounts::Accounts<C>,
-}
 
-// TODO add macro to generate the following code.
-impl<C: Context> Runtime<C> {
-    pub(crate) fn new() -> Self {
-        Self {
-            election: election::Election::new(),
-            value_setter: value_setter::ValueSetter::new(),
-            accounts: accounts::Accounts::new(),
-        }
-    }
+    #[allow(unused)]
+    bank: bank::Bank<C>,
 }
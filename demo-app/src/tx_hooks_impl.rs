@@ -8,6 +8,7 @@ pub(crate) struct AppVerifiedTx<C: Context> {
     pub(crate) pub_key: C::PublicKey,
     pub(crate) sender: C::Address,
     pub(crate) runtime_msg: Vec<u8>,
+    pub(crate) gas_limit: u64,
 }
 
 impl<C: Context> VerifiedTx for AppVerifiedTx<C> {
@@ -20,6 +21,10 @@ impl<C: Context> VerifiedTx for AppVerifiedTx<C> {
     fn runtime_message(&self) -> &[u8] {
         &self.runtime_msg
     }
+
+    fn gas_limit(&self) -> u64 {
+        self.gas_limit
+    }
 }
 
 pub(crate) struct DemoAppTxHooks<C: Context> {
@@ -50,6 +55,7 @@ impl<C: Context> TxHooks for DemoAppTxHooks<C> {
             pub_key: tx.pub_key,
             sender: addr,
             runtime_msg: tx.runtime_msg,
+            gas_limit: tx.gas_limit,
         })
     }
 
@@ -11,15 +11,23 @@ pub(crate) struct Transaction<C: sov_modules_api::Context> {
     pub(crate) pub_key: C::PublicKey,
     pub(crate) runtime_msg: Vec<u8>,
     pub(crate) nonce: u64,
+    pub(crate) gas_limit: u64,
 }
 
 impl<C: sov_modules_api::Context> Transaction<C> {
-    pub fn new(msg: Vec<u8>, pub_key: C::PublicKey, signature: C::Signature, nonce: u64) -> Self {
+    pub fn new(
+        msg: Vec<u8>,
+        pub_key: C::PublicKey,
+        signature: C::Signature,
+        nonce: u64,
+        gas_limit: u64,
+    ) -> Self {
         Self {
             signature,
             runtime_msg: msg,
             pub_key,
             nonce,
+            gas_limit,
         }
     }
 }
@@ -43,10 +51,11 @@ impl<C: Context> TxVerifier for DemoAppTxVerifier<C> {
         let mut data = Cursor::new(&raw_tx.data);
         let tx = Transaction::<C>::decode(&mut data)?;
 
-        // We check signature against runtime_msg and nonce.
+        // We check signature against runtime_msg, nonce and gas_limit.
         let mut hasher = C::Hasher::new();
         hasher.update(&tx.runtime_msg);
         hasher.update(&tx.nonce.to_le_bytes());
+        hasher.update(&tx.gas_limit.to_le_bytes());
         let msg_hash = hasher.finalize();
 
         tx.signature.verify(&tx.pub_key, msg_hash)?;
@@ -70,6 +70,16 @@ impl CacheLog {
         }
     }
 
+    /// Returns the last value of every key in the cache that starts with `prefix`.
+    /// The order of the returned entries is unspecified.
+    pub fn get_values_with_prefix(&self, prefix: &[u8]) -> Vec<(CacheKey, Option<CacheValue>)> {
+        self.log
+            .iter()
+            .filter(|(k, _)| k.key.starts_with(prefix))
+            .map(|(k, v)| (k.clone(), v.last_value().clone()))
+            .collect()
+    }
+
     /// The first read for a given key is inserted in the cache. For an existing cache entry
     /// checks if reads are consistent with previous reads/writes.
     pub fn add_read(&mut self, key: CacheKey, value: Option<CacheValue>) -> Result<(), ReadError> {
@@ -215,6 +225,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_get_values_with_prefix() {
+        let mut cache_log = CacheLog::default();
+        let prefixed_key = |k: u8| CacheKey {
+            key: std::sync::Arc::new(vec![7, k]),
+        };
+
+        cache_log
+            .add_read(prefixed_key(1), create_value(1))
+            .unwrap();
+        cache_log.add_write(prefixed_key(2), create_value(2));
+        cache_log.add_write(prefixed_key(3), None);
+        cache_log.add_write(create_key(7), create_value(4));
+        cache_log.add_write(create_key(8), create_value(5));
+
+        let mut values = cache_log.get_values_with_prefix(&[7]);
+        values.sort_by(|(a, _), (b, _)| a.key.cmp(&b.key));
+
+        assert_eq!(
+            values,
+            vec![
+                (create_key(7), create_value(4)),
+                (prefixed_key(1), create_value(1)),
+                (prefixed_key(2), create_value(2)),
+                (prefixed_key(3), None),
+            ]
+        );
+    }
+
     #[derive(PartialEq, Eq, Clone, Debug)]
     pub(crate) struct CacheEntry {
         key: CacheKey,
@@ -1,8 +1,19 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use bytes::Bytes;
 
-use crate::zk::traits::{Matches, ProofTrait, ZkVm};
+use crate::{
+    da::{BlobTransactionTrait, BlockHashTrait, DaLayerTrait},
+    serial::{Decode, DecodeBorrowed, DeserializationError, Encode},
+    stf::{BatchReceipt, ConsensusSetUpdate, OpaqueAddress, SlotInfo, StateTransitionFunction},
+    zk::traits::{Matches, ProofTrait, ZkVm},
+};
 
-use super::{traits::Witness, types::ArrayWitness};
+use super::{
+    traits::{
+        AddressTrait, BatchTrait, BlockheaderTrait, CanonicalHash, TransactionTrait, Witness,
+    },
+    types::ArrayWitness,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
 pub struct MockCodeCommitment(pub [u8; 32]);
@@ -55,3 +66,229 @@ impl ZkVm for MockZkvm {
         proof.verify(code_commitment)
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockHash(pub [u8; 32]);
+
+impl AsRef<[u8]> for MockHash {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Encode for MockHash {
+    fn encode(&self, target: &mut impl std::io::Write) {
+        target
+            .write_all(&self.0)
+            .expect("Serialization is infallible");
+    }
+}
+
+impl Decode for MockHash {
+    type Error = DeserializationError;
+
+    fn decode<R: std::io::Read>(target: &mut R) -> Result<Self, <Self as Decode>::Error> {
+        let mut hash = [0u8; 32];
+        let mut got = 0;
+        while got < hash.len() {
+            match target.read(&mut hash[got..]) {
+                Ok(0) | Err(_) => {
+                    return Err(DeserializationError::DataTooShort { expected: 32, got })
+                }
+                Ok(n) => got += n,
+            }
+        }
+        Ok(Self(hash))
+    }
+}
+
+impl<'de> DecodeBorrowed<'de> for MockHash {
+    type Error = DeserializationError;
+
+    fn decode_from_slice(target: &'de [u8]) -> Result<Self, Self::Error> {
+        Self::decode(&mut &target[..])
+    }
+}
+
+impl BlockHashTrait for MockHash {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockAddress(pub [u8; 32]);
+
+impl AsRef<[u8]> for MockAddress {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for MockAddress {
+    type Error = anyhow::Error;
+
+    fn try_from(addr: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self(addr.try_into()?))
+    }
+}
+
+impl AddressTrait for MockAddress {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockBlockHeader {
+    pub prev_hash: MockHash,
+    pub hash: MockHash,
+    pub height: u64,
+    pub timestamp: u64,
+}
+
+impl CanonicalHash for MockBlockHeader {
+    type Output = MockHash;
+
+    fn hash(&self) -> Self::Output {
+        self.hash.clone()
+    }
+}
+
+impl BlockheaderTrait for MockBlockHeader {
+    type Hash = MockHash;
+
+    fn prev_hash(&self) -> &Self::Hash {
+        &self.prev_hash
+    }
+
+    fn height(&self) -> u64 {
+        self.height
+    }
+
+    fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockBlob {
+    pub sender: MockAddress,
+    pub data: Bytes,
+}
+
+impl BlobTransactionTrait<MockAddress> for MockBlob {
+    type Data = Bytes;
+
+    fn sender(&self) -> MockAddress {
+        self.sender.clone()
+    }
+
+    fn data(&self) -> Self::Data {
+        self.data.clone()
+    }
+}
+
+/// A DA layer without any blocks, which accepts every list of transactions.
+pub struct MockDaLayer;
+
+impl DaLayerTrait for MockDaLayer {
+    type Blockhash = MockHash;
+    type Address = MockAddress;
+    type BlockHeader = MockBlockHeader;
+    type BlobTransaction = MockBlob;
+    type InclusionMultiProof = ();
+    type CompletenessProof = ();
+    type Error = ();
+
+    const ADDRESS_LENGTH: usize = 32;
+    const RELATIVE_GENESIS: Self::Blockhash = MockHash([0; 32]);
+
+    fn get_relevant_txs(&self, _blockhash: &Self::Blockhash) -> Vec<Self::BlobTransaction> {
+        Vec::new()
+    }
+
+    fn get_relevant_txs_with_proof(
+        &self,
+        _blockhash: &Self::Blockhash,
+    ) -> (
+        Vec<Self::BlobTransaction>,
+        Self::InclusionMultiProof,
+        Self::CompletenessProof,
+    ) {
+        (Vec::new(), (), ())
+    }
+
+    fn verify_relevant_tx_list(
+        &self,
+        _blockheader: &Self::BlockHeader,
+        _txs: &[Self::BlobTransaction],
+        _inclusion_proof: Self::InclusionMultiProof,
+        _completeness_proof: Self::CompletenessProof,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct MockTransaction(pub Vec<u8>);
+
+impl CanonicalHash for MockTransaction {
+    type Output = [u8; 32];
+
+    fn hash(&self) -> Self::Output {
+        super::crypto::hash::sha2(&self.0).0
+    }
+}
+
+impl TransactionTrait for MockTransaction {
+    type Hash = [u8; 32];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct MockBatch(pub Vec<MockTransaction>);
+
+impl BatchTrait for MockBatch {
+    type Transaction = MockTransaction;
+
+    fn transactions(&self) -> &[Self::Transaction] {
+        &self.0
+    }
+
+    fn take_transactions(self) -> Vec<Self::Transaction> {
+        self.0
+    }
+}
+
+/// A state transition function which ignores its input and keeps a constant state root.
+#[derive(Default)]
+pub struct MockStf;
+
+impl StateTransitionFunction for MockStf {
+    type StateRoot = [u8; 32];
+    type ChainParams = ();
+    type Transaction = MockTransaction;
+    type Batch = MockBatch;
+    type Proof = MockProof;
+    type MisbehaviorProof = ();
+
+    fn init_chain(&mut self, _params: Self::ChainParams) {}
+
+    fn begin_slot(&mut self, _slot: SlotInfo) {}
+
+    fn apply_batch(
+        &mut self,
+        _batch: Self::Batch,
+        _sequencer: &[u8],
+        _misbehavior_hint: Option<Self::MisbehaviorProof>,
+    ) -> Result<BatchReceipt, ConsensusSetUpdate<OpaqueAddress>> {
+        Ok(BatchReceipt {
+            batch_hash: [0; 32],
+            tx_receipts: Vec::new(),
+        })
+    }
+
+    fn apply_proof(
+        &self,
+        _proof: Self::Proof,
+        _prover: &[u8],
+    ) -> Result<(), ConsensusSetUpdate<OpaqueAddress>> {
+        Ok(())
+    }
+
+    fn end_slot(&mut self) -> (Self::StateRoot, Vec<ConsensusSetUpdate<OpaqueAddress>>) {
+        ([0; 32], Vec::new())
+    }
+}
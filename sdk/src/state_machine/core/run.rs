@@ -1,15 +1,19 @@
 use bytes::Buf;
 
 use crate::{
-    core::traits::{BlockheaderTrait, CanonicalHash},
+    core::{
+        crypto::hash::{sha2, DefaultHash},
+        traits::{BlockheaderTrait, CanonicalHash},
+    },
     da::{BlobTransactionTrait, DaLayerTrait},
-    serial::DecodeBorrowed,
+    serial::{Decode, DecodeBorrowed, Encode},
     state_machine::env,
-    stf::{ConsensusMessage, StateTransitionFunction},
+    stf::{BatchReceipt, ConsensusMessage, ConsensusRole, SlotInfo, StateTransitionFunction},
+    utils::tx_trie::TxTrie,
     zk::traits::{ProofTrait, RecursiveProofInput, ZkVm},
 };
 
-use super::types::RollupHeader;
+use super::types::{ConsensusParticipantRoot, RollupHeader};
 
 pub struct Rollup<DaLayer: DaLayerTrait, App: StateTransitionFunction> {
     pub da_layer: DaLayer,
@@ -44,6 +48,8 @@ pub struct BlockProof<Vm: ZkVm, DaLayer: DaLayerTrait, App: StateTransitionFunct
 
 impl<Vm: ZkVm<Proof = Self>, DaLayer: DaLayerTrait, App: StateTransitionFunction> ProofTrait<Vm>
     for BlockProof<Vm, DaLayer, App>
+where
+    App::StateRoot: Encode + Decode,
 {
     type Output = RollupHeader<DaLayer, App>;
 
@@ -55,7 +61,10 @@ impl<Vm: ZkVm<Proof = Self>, DaLayer: DaLayerTrait, App: StateTransitionFunction
     }
 }
 
-impl<DaLayer: DaLayerTrait, App: StateTransitionFunction> Rollup<DaLayer, App> {
+impl<DaLayer: DaLayerTrait, App: StateTransitionFunction> Rollup<DaLayer, App>
+where
+    App::StateRoot: Encode + Decode,
+{
     pub fn zk_verify_block<Vm: ZkVm<Proof = BlockProof<Vm, DaLayer, App>>>(
         &mut self,
     ) -> Result<BlockProof<Vm, DaLayer, App>, Vm::Error> {
@@ -102,27 +111,51 @@ impl<DaLayer: DaLayerTrait, App: StateTransitionFunction> Rollup<DaLayer, App> {
 
         let mut current_sequencers = prev_header.sequencers_root.clone();
         let mut current_provers = prev_header.provers_root.clone();
+        // Registered sets are committed to by their root only, the host provides their participants.
+        for participants in [&mut current_sequencers, &mut current_provers] {
+            if let ConsensusParticipantRoot::Registered(_) = participants {
+                participants
+                    .load_participants(env::read_unchecked())
+                    .expect("Host must provide correct data");
+            }
+        }
 
-        self.app.begin_slot();
+        self.app.begin_slot(SlotInfo {
+            da_height: current_da_header.height(),
+            da_hash: sha2(&current_da_header.hash().encode_to_vec()).0,
+            timestamp: current_da_header.timestamp(),
+        });
+        let mut applied_batches = Vec::new();
         for tx in relevant_txs {
             let mut data = tx.data();
             let len = data.remaining();
             let data = data.copy_to_bytes(len);
             match ConsensusMessage::decode_from_slice(&data[..]).unwrap() {
                 ConsensusMessage::Batch(batch) => {
-                    if current_sequencers.allows(tx.sender()) {
+                    if current_sequencers
+                        .allows(tx.sender())
+                        .expect("Registered sets are loaded")
+                    {
                         match self.app.apply_batch(batch, tx.sender().as_ref(), None) {
-                            // TODO: handle events
-                            Ok(_events) => {}
-                            Err(slashing) => current_sequencers.process_update(slashing),
+                            // The receipts are committed to by the applied txs root only, their events
+                            // and bodies are stored by the full node.
+                            Ok(receipt) => applied_batches.push(receipt),
+                            Err(slashing) => current_sequencers
+                                .process_update(slashing)
+                                .expect("Registered sets are loaded"),
                         };
                     }
                 }
                 ConsensusMessage::Proof(p) => {
-                    if current_provers.allows(tx.sender()) {
+                    if current_provers
+                        .allows(tx.sender())
+                        .expect("Registered sets are loaded")
+                    {
                         match self.app.apply_proof(p, tx.sender().as_ref()) {
                             Ok(()) => {}
-                            Err(slashing) => current_provers.process_update(slashing),
+                            Err(slashing) => current_provers
+                                .process_update(slashing)
+                                .expect("Registered sets are loaded"),
                         };
                     }
                 }
@@ -130,21 +163,12 @@ impl<DaLayer: DaLayerTrait, App: StateTransitionFunction> Rollup<DaLayer, App> {
         }
         let (app_hash, consensus_updates) = self.app.end_slot();
         for update in consensus_updates {
-            if let Some(role) = &update.new_role {
-                match role {
-                    crate::stf::ConsensusRole::Prover => {
-                        current_provers.process_update(update);
-                        continue;
-                    }
-                    crate::stf::ConsensusRole::Sequencer => {
-                        current_provers.process_update(update);
-                        continue;
-                    }
-                    crate::stf::ConsensusRole::ProverAndSequencer => {}
-                }
-            }
-            current_provers.process_update(update.clone());
-            current_sequencers.process_update(update);
+            current_sequencers
+                .process_update(update.for_role(ConsensusRole::Sequencer))
+                .expect("Registered sets are loaded");
+            current_provers
+                .process_update(update.for_role(ConsensusRole::Prover))
+                .expect("Registered sets are loaded");
         }
         current_provers.finalize();
         current_sequencers.finalize();
@@ -154,7 +178,7 @@ impl<DaLayer: DaLayerTrait, App: StateTransitionFunction> Rollup<DaLayer, App> {
             sequencers_root: current_sequencers,
             provers_root: current_provers,
             app_root: app_hash,
-            applied_txs_root: Default::default(), // TODO!,
+            applied_txs_root: applied_txs_root(&applied_batches),
             prev_hash: prev_header.hash(),
         };
         Ok(BlockProof {
@@ -168,3 +192,56 @@ impl<DaLayer: DaLayerTrait, App: StateTransitionFunction> Rollup<DaLayer, App> {
     //     // prev_proof.recurse()
     // }
 }
+
+/// Returns the root of the [`TxTrie`] over the hashes of the transactions of the applied batches, in order.
+fn applied_txs_root(applied_batches: &[BatchReceipt]) -> DefaultHash {
+    TxTrie::new(
+        applied_batches
+            .iter()
+            .flat_map(|batch| batch.tx_receipts.iter().map(|receipt| receipt.tx_hash)),
+    )
+    .root()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stf::{TransactionReceipt, TxStatus};
+
+    fn batch_receipt(tx_hashes: &[[u8; 32]]) -> BatchReceipt {
+        BatchReceipt {
+            batch_hash: [0; 32],
+            tx_receipts: tx_hashes
+                .iter()
+                .map(|tx_hash| TransactionReceipt {
+                    tx_hash: *tx_hash,
+                    body: vec![],
+                    status: TxStatus::Applied,
+                    events: vec![],
+                    error: None,
+                    gas_used: 0,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_applied_txs_root_covers_every_batch() {
+        let batches = [
+            batch_receipt(&[[1; 32], [2; 32]]),
+            batch_receipt(&[[3; 32]]),
+        ];
+        let trie = TxTrie::new([[1; 32], [2; 32], [3; 32]]);
+        let root = applied_txs_root(&batches);
+        assert_eq!(root, trie.root());
+
+        // A transaction of the last batch can be proven against the root of the header.
+        assert!(trie.prove(2).unwrap().verify(&root, [3; 32]));
+
+        assert_eq!(
+            applied_txs_root(&[]),
+            TxTrie::new(Vec::<[u8; 32]>::new()).root()
+        );
+        assert_ne!(applied_txs_root(&batches[..1]), root);
+    }
+}
@@ -10,6 +10,10 @@ use crate::serial::{Decode, Encode};
 pub trait BlockheaderTrait: PartialEq + Debug + CanonicalHash<Output = Self::Hash> {
     type Hash: Clone;
     fn prev_hash(&self) -> &Self::Hash;
+    /// The height of the block on the DA layer.
+    fn height(&self) -> u64;
+    /// The time at which the block was produced, in seconds since the unix epoch.
+    fn timestamp(&self) -> u64;
 }
 
 pub trait CanonicalHash {
@@ -23,9 +27,9 @@ pub trait BatchTrait: PartialEq + Debug + Encode + Decode + Clone {
     fn take_transactions(self) -> Vec<Self::Transaction>;
 }
 
-pub trait TransactionTrait:
-    PartialEq + Debug + CanonicalHash<Output = Self::Hash> + Encode + Decode
-{
+/// The hash of a transaction isn't part of the trait: it depends on the hasher of the rollup,
+/// which the state transition function chooses.
+pub trait TransactionTrait: PartialEq + Debug + Encode + Decode {
     type Hash: AsRef<[u8]>;
 }
 
@@ -1,13 +1,19 @@
 use std::{cell::RefCell, sync::atomic::AtomicUsize};
 
+use borsh::{BorshDeserialize, BorshSerialize};
+use thiserror::Error;
+
 use crate::{
     da::DaLayerTrait,
     maybestd::rc::Rc,
-    serial::{Decode, DecodeBorrowed, Encode},
-    stf::{ConsensusSetUpdate, StateTransitionFunction},
+    serial::{Decode, DecodeBorrowed, DeserializationError, Encode},
+    stf::{ConsensusRole, ConsensusSetUpdate, StateTransitionFunction},
 };
 
-use super::{crypto::hash::DefaultHash, traits::Witness};
+use super::{
+    crypto::hash::{sha2, DefaultHash},
+    traits::Witness,
+};
 
 /// A block header of the *logical* chain created by running a particular state transition
 /// function over a particular DA application.
@@ -31,31 +37,113 @@ pub struct RollupHeader<DaLayer: DaLayerTrait, App: StateTransitionFunction> {
     pub prev_hash: DefaultHash,
 }
 
-impl<D: DaLayerTrait, A: StateTransitionFunction> Encode for RollupHeader<D, A> {
-    fn encode(&self, _target: &mut impl std::io::Write) {
-        todo!()
+impl<D: DaLayerTrait, A: StateTransitionFunction> core::fmt::Debug for RollupHeader<D, A>
+where
+    A::StateRoot: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("RollupHeader")
+            .field("da_blockhash", &self.da_blockhash)
+            .field("sequencers_root", &self.sequencers_root)
+            .field("provers_root", &self.provers_root)
+            .field("app_root", &self.app_root)
+            .field("applied_txs_root", &self.applied_txs_root)
+            .field("prev_hash", &self.prev_hash)
+            .finish()
     }
 }
 
-impl<D: DaLayerTrait, A: StateTransitionFunction> Decode for RollupHeader<D, A> {
-    type Error = ();
+impl<D: DaLayerTrait, A: StateTransitionFunction> PartialEq for RollupHeader<D, A>
+where
+    A::StateRoot: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.da_blockhash == other.da_blockhash
+            && self.sequencers_root == other.sequencers_root
+            && self.provers_root == other.provers_root
+            && self.app_root == other.app_root
+            && self.applied_txs_root == other.applied_txs_root
+            && self.prev_hash == other.prev_hash
+    }
+}
+
+/// The version of the binary encoding of [`RollupHeader`]. It is the first byte of every encoded
+/// header and must be bumped whenever the layout changes.
+pub const ROLLUP_HEADER_VERSION: u8 = 0;
+
+/// Error type for decoding a [`RollupHeader`].
+#[derive(Debug, Error)]
+pub enum RollupHeaderDecodeError {
+    #[error("Unsupported rollup header version: {0}")]
+    UnsupportedVersion(u8),
+    #[error("Invalid consensus participant address: {0}")]
+    InvalidAddress(anyhow::Error),
+    #[error("Invalid app root: {0}")]
+    InvalidAppRoot(String),
+    #[error(transparent)]
+    Deserialization(#[from] DeserializationError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// The header is encoded as the version byte followed by the fields in declaration order.
+impl<D: DaLayerTrait, A: StateTransitionFunction> Encode for RollupHeader<D, A>
+where
+    A::StateRoot: Encode,
+{
+    fn encode(&self, target: &mut impl std::io::Write) {
+        ROLLUP_HEADER_VERSION.encode(target);
+        self.da_blockhash.encode(target);
+        self.sequencers_root.encode(target);
+        self.provers_root.encode(target);
+        self.app_root.encode(target);
+        self.applied_txs_root.encode(target);
+        self.prev_hash.encode(target);
+    }
+}
+
+impl<D: DaLayerTrait, A: StateTransitionFunction> Decode for RollupHeader<D, A>
+where
+    A::StateRoot: Decode,
+{
+    type Error = RollupHeaderDecodeError;
+
+    fn decode<R: std::io::Read>(target: &mut R) -> Result<Self, <Self as Decode>::Error> {
+        let version = u8::deserialize_reader(target)?;
+        if version != ROLLUP_HEADER_VERSION {
+            return Err(RollupHeaderDecodeError::UnsupportedVersion(version));
+        }
 
-    fn decode<R: std::io::Read>(_target: &mut R) -> Result<Self, <Self as Decode>::Error> {
-        todo!()
+        Ok(Self {
+            da_blockhash: D::Blockhash::decode(target)?,
+            sequencers_root: ConsensusParticipantRoot::decode_from_reader(target)?,
+            provers_root: ConsensusParticipantRoot::decode_from_reader(target)?,
+            app_root: A::StateRoot::decode(target)
+                .map_err(|e| RollupHeaderDecodeError::InvalidAppRoot(format!("{e:?}")))?,
+            applied_txs_root: DefaultHash::deserialize_reader(target)?,
+            prev_hash: DefaultHash::deserialize_reader(target)?,
+        })
     }
 }
 
-impl<'de, D: DaLayerTrait, A: StateTransitionFunction> DecodeBorrowed<'de> for RollupHeader<D, A> {
-    type Error = ();
+impl<'de, D: DaLayerTrait, A: StateTransitionFunction> DecodeBorrowed<'de> for RollupHeader<D, A>
+where
+    A::StateRoot: Decode,
+{
+    type Error = RollupHeaderDecodeError;
 
-    fn decode_from_slice(_target: &'de [u8]) -> Result<Self, Self::Error> {
-        todo!()
+    fn decode_from_slice(target: &'de [u8]) -> Result<Self, Self::Error> {
+        Self::decode(&mut &target[..])
     }
 }
 
-impl<DaLayer: DaLayerTrait, App: StateTransitionFunction> RollupHeader<DaLayer, App> {
+impl<DaLayer: DaLayerTrait, App: StateTransitionFunction> RollupHeader<DaLayer, App>
+where
+    App::StateRoot: Encode,
+{
+    /// Hashes the encoding of the header.
     pub fn hash(&self) -> DefaultHash {
-        todo!()
+        sha2(&self.encode_to_vec())
     }
 }
 
@@ -92,7 +180,7 @@ pub struct AugmentedRollupBlock<DaLayer: DaLayerTrait, App: StateTransitionFunct
     pub completeness_proof: DaLayer::CompletenessProof,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ConsensusParticipantRoot<Addr> {
     /// Anyone is allowed to participate in consensus of the rollup
     Anyone,
@@ -100,35 +188,242 @@ pub enum ConsensusParticipantRoot<Addr> {
     Centralized(Addr),
     /// The set of allowed participants is registered. It may or may not change over time
     Registered(DefaultHash),
+    /// A registered set whose participants are known, so it can be checked and updated.
+    /// [`ConsensusParticipantRoot::finalize`] commits to it as `Registered`.
+    RegisteredSet(RegisteredParticipants<Addr>),
 }
 
-impl<Addr: PartialEq> ConsensusParticipantRoot<Addr> {
-    pub fn allows(&self, participant: Addr) -> bool {
+/// The participants of a registered consensus set and their roles, kept sorted by address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisteredParticipants<Addr> {
+    participants: Vec<(Addr, ConsensusRole)>,
+}
+
+impl<Addr: AsRef<[u8]>> RegisteredParticipants<Addr> {
+    /// Creates the set. If an address is listed several times, its first role is kept.
+    pub fn new(mut participants: Vec<(Addr, ConsensusRole)>) -> Self {
+        participants.sort_by(|(a, _), (b, _)| a.as_ref().cmp(b.as_ref()));
+        participants.dedup_by(|(a, _), (b, _)| a.as_ref() == b.as_ref());
+        Self { participants }
+    }
+
+    pub fn contains(&self, participant: &Addr) -> bool {
+        self.position(participant).is_ok()
+    }
+
+    /// Returns the role of the participant, or None if it isn't registered.
+    pub fn role(&self, participant: &Addr) -> Option<ConsensusRole> {
+        self.position(participant)
+            .ok()
+            .map(|index| self.participants[index].1)
+    }
+
+    /// Registers the participant with the given role, replacing its previous role.
+    /// Returns false if the participant already had that role.
+    pub fn insert(&mut self, participant: Addr, role: ConsensusRole) -> bool {
+        match self.position(&participant) {
+            Ok(index) => {
+                let changed = self.participants[index].1 != role;
+                self.participants[index].1 = role;
+                changed
+            }
+            Err(index) => {
+                self.participants.insert(index, (participant, role));
+                true
+            }
+        }
+    }
+
+    /// Removes the participant from the set. Returns false if it wasn't registered.
+    pub fn remove(&mut self, participant: &Addr) -> bool {
+        match self.position(participant) {
+            Ok(index) => {
+                self.participants.remove(index);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    pub fn participants(&self) -> &[(Addr, ConsensusRole)] {
+        &self.participants
+    }
+
+    /// Commits to the set: the hash of the number of participants followed by
+    /// their length-prefixed addresses and their roles, in order.
+    pub fn root(&self) -> DefaultHash {
+        let mut encoded = Vec::new();
+        (self.participants.len() as u32).encode(&mut encoded);
+        for (participant, role) in &self.participants {
+            BorshSerialize::serialize(participant.as_ref(), &mut encoded)
+                .expect("Serialization is infallible");
+            role.encode(&mut encoded);
+        }
+        sha2(&encoded)
+    }
+
+    fn position(&self, participant: &Addr) -> Result<usize, usize> {
+        self.participants
+            .binary_search_by(|(p, _)| p.as_ref().cmp(participant.as_ref()))
+    }
+}
+
+/// Error type for checking and updating a [`ConsensusParticipantRoot`].
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ConsensusSetError {
+    #[error("The participants of the registered set must be loaded first")]
+    ParticipantsNotLoaded,
+}
+
+const ANYONE_TAG: u8 = 0;
+const CENTRALIZED_TAG: u8 = 1;
+const REGISTERED_TAG: u8 = 2;
+
+/// Encoded as a tag byte, followed by the length-prefixed address for `Centralized`
+/// or by the root hash for `Registered`. A `RegisteredSet` is encoded as the `Registered` root it commits to,
+/// so it decodes as that root: the participants aren't part of the encoding.
+impl<Addr: AsRef<[u8]>> Encode for ConsensusParticipantRoot<Addr> {
+    fn encode(&self, target: &mut impl std::io::Write) {
         match self {
-            ConsensusParticipantRoot::Anyone => true,
-            ConsensusParticipantRoot::Centralized(allowed_addr) => &participant == allowed_addr,
-            ConsensusParticipantRoot::Registered(_) => todo!(),
+            ConsensusParticipantRoot::Anyone => ANYONE_TAG.encode(target),
+            ConsensusParticipantRoot::Centralized(addr) => {
+                CENTRALIZED_TAG.encode(target);
+                BorshSerialize::serialize(addr.as_ref(), target)
+                    .expect("Serialization is infallible");
+            }
+            ConsensusParticipantRoot::Registered(root) => {
+                REGISTERED_TAG.encode(target);
+                root.encode(target);
+            }
+            ConsensusParticipantRoot::RegisteredSet(set) => {
+                REGISTERED_TAG.encode(target);
+                set.root().encode(target);
+            }
         }
     }
+}
+
+impl<Addr: for<'a> TryFrom<&'a [u8], Error = anyhow::Error>> ConsensusParticipantRoot<Addr> {
+    fn decode_from_reader<R: std::io::Read>(
+        target: &mut R,
+    ) -> Result<Self, RollupHeaderDecodeError> {
+        Ok(match u8::deserialize_reader(target)? {
+            ANYONE_TAG => ConsensusParticipantRoot::Anyone,
+            CENTRALIZED_TAG => {
+                let addr = Vec::<u8>::deserialize_reader(target)?;
+                ConsensusParticipantRoot::Centralized(
+                    Addr::try_from(&addr[..]).map_err(RollupHeaderDecodeError::InvalidAddress)?,
+                )
+            }
+            REGISTERED_TAG => {
+                ConsensusParticipantRoot::Registered(DefaultHash::deserialize_reader(target)?)
+            }
+            got => Err(DeserializationError::InvalidTag {
+                max_allowed: REGISTERED_TAG,
+                got,
+            })?,
+        })
+    }
+}
+
+impl<Addr> ConsensusParticipantRoot<Addr>
+where
+    Addr: PartialEq + Clone + AsRef<[u8]> + for<'a> TryFrom<&'a [u8], Error = anyhow::Error>,
+{
+    /// Provides the participants a `Registered` root commits to, so that the set can be checked
+    /// and updated. Fails if the participants don't match the root.
+    pub fn load_participants(
+        &mut self,
+        participants: Vec<(Addr, ConsensusRole)>,
+    ) -> anyhow::Result<()> {
+        let ConsensusParticipantRoot::Registered(root) = self else {
+            anyhow::bail!("Only the participants of a registered set can be loaded");
+        };
+
+        let set = RegisteredParticipants::new(participants);
+        anyhow::ensure!(
+            &set.root() == root,
+            "Participants don't match the root of the registered set"
+        );
+        *self = ConsensusParticipantRoot::RegisteredSet(set);
+        Ok(())
+    }
 
-    pub fn process_update(&mut self, _updates: ConsensusSetUpdate<Rc<Vec<u8>>>) {
+    /// Fails if the root is `Registered` and its participants weren't loaded.
+    pub fn allows(&self, participant: Addr) -> Result<bool, ConsensusSetError> {
         match self {
-            ConsensusParticipantRoot::Anyone => todo!(),
-            ConsensusParticipantRoot::Centralized(_) => todo!(),
-            ConsensusParticipantRoot::Registered(_) => todo!(),
+            ConsensusParticipantRoot::Anyone => Ok(true),
+            ConsensusParticipantRoot::Centralized(allowed_addr) => Ok(&participant == allowed_addr),
+            ConsensusParticipantRoot::Registered(_) => {
+                Err(ConsensusSetError::ParticipantsNotLoaded)
+            }
+            ConsensusParticipantRoot::RegisteredSet(set) => Ok(set.contains(&participant)),
         }
     }
-    pub fn process_updates(&mut self, _updates: Vec<ConsensusSetUpdate<Rc<Vec<u8>>>>) {
-        // for item in vec.map(|| to_address).then(...)
+
+    /// Applies an update to the set: the address is registered with its new role,
+    /// and removed (e.g. slashed) if `new_role` is None. Updates with an invalid address are ignored.
+    ///
+    /// Updates have no effect when anyone is allowed to participate. A centralized set stays centralized
+    /// as long as the updates don't change its participants: once another address is added, it becomes
+    /// a registered set where the centralized participant gets the role of that address. Updates restricted
+    /// with [`ConsensusSetUpdate::for_role`] all carry the role of the set.
+    ///
+    /// Fails if the root is `Registered` and its participants weren't loaded.
+    pub fn process_update(
+        &mut self,
+        update: ConsensusSetUpdate<Rc<Vec<u8>>>,
+    ) -> Result<(), ConsensusSetError> {
+        let Ok(address) = Addr::try_from(&update.address[..]) else {
+            return Ok(());
+        };
+
         match self {
-            ConsensusParticipantRoot::Anyone => todo!(),
-            ConsensusParticipantRoot::Centralized(_) => todo!(),
-            ConsensusParticipantRoot::Registered(_) => todo!(),
+            ConsensusParticipantRoot::Anyone => {}
+            ConsensusParticipantRoot::Centralized(addr) => match update.new_role {
+                Some(role) if &address != addr => {
+                    *self =
+                        ConsensusParticipantRoot::RegisteredSet(RegisteredParticipants::new(vec![
+                            (addr.clone(), role),
+                            (address, role),
+                        ]));
+                }
+                None if &address == addr => {
+                    *self = ConsensusParticipantRoot::RegisteredSet(RegisteredParticipants::new(
+                        vec![],
+                    ));
+                }
+                // The centralized participant keeps its place, or an unknown address is removed
+                _ => {}
+            },
+            ConsensusParticipantRoot::Registered(_) => {
+                return Err(ConsensusSetError::ParticipantsNotLoaded)
+            }
+            ConsensusParticipantRoot::RegisteredSet(set) => {
+                match update.new_role {
+                    Some(role) => set.insert(address, role),
+                    None => set.remove(&address),
+                };
+            }
         }
+        Ok(())
     }
 
+    pub fn process_updates(
+        &mut self,
+        updates: Vec<ConsensusSetUpdate<Rc<Vec<u8>>>>,
+    ) -> Result<(), ConsensusSetError> {
+        for update in updates {
+            self.process_update(update)?;
+        }
+        Ok(())
+    }
+
+    /// Replaces a `RegisteredSet` by the `Registered` root committing to it.
     pub fn finalize(&mut self) {
-        todo!()
+        if let ConsensusParticipantRoot::RegisteredSet(set) = self {
+            *self = ConsensusParticipantRoot::Registered(set.root());
+        }
     }
 }
 
@@ -158,3 +453,257 @@ impl Witness for ArrayWitness {
             .extend(rhs.hints.borrow_mut().drain(rhs_next_idx..))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::core::{
+        crypto::hash::Sha2Hash,
+        mocks::{MockAddress, MockDaLayer, MockHash, MockStf},
+    };
+    use crate::stf::ConsensusRole;
+
+    type MockHeader = RollupHeader<MockDaLayer, MockStf>;
+
+    fn arb_participant_root() -> impl Strategy<Value = ConsensusParticipantRoot<MockAddress>> {
+        prop_oneof![
+            Just(ConsensusParticipantRoot::Anyone),
+            any::<[u8; 32]>().prop_map(|a| ConsensusParticipantRoot::Centralized(MockAddress(a))),
+            any::<[u8; 32]>().prop_map(|h| ConsensusParticipantRoot::Registered(Sha2Hash(h))),
+        ]
+    }
+
+    prop_compose! {
+        fn arb_header()(
+            da_blockhash in any::<[u8; 32]>(),
+            sequencers_root in arb_participant_root(),
+            provers_root in arb_participant_root(),
+            app_root in any::<[u8; 32]>(),
+            applied_txs_root in any::<[u8; 32]>(),
+            prev_hash in any::<[u8; 32]>(),
+        ) -> MockHeader {
+            RollupHeader {
+                da_blockhash: MockHash(da_blockhash),
+                sequencers_root,
+                provers_root,
+                app_root,
+                applied_txs_root: Sha2Hash(applied_txs_root),
+                prev_hash: Sha2Hash(prev_hash),
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_header_round_trip(header in arb_header()) {
+            let encoded = header.encode_to_vec();
+            prop_assert_eq!(encoded[0], ROLLUP_HEADER_VERSION);
+
+            let decoded = MockHeader::decode_from_slice(&encoded).unwrap();
+            prop_assert_eq!(decoded.hash(), header.hash());
+            prop_assert_eq!(decoded, header);
+        }
+
+        #[test]
+        fn test_header_decoding_does_not_panic(bytes in proptest::collection::vec(any::<u8>(), 0..256)) {
+            let _ = MockHeader::decode_from_slice(&bytes);
+        }
+    }
+
+    #[test]
+    fn test_registered_set_decodes_as_its_root() {
+        let set =
+            RegisteredParticipants::new(vec![(MockAddress([1; 32]), ConsensusRole::Sequencer)]);
+        let root = set.root();
+        let header = MockHeader {
+            da_blockhash: MockHash([1; 32]),
+            sequencers_root: ConsensusParticipantRoot::RegisteredSet(set),
+            provers_root: ConsensusParticipantRoot::Anyone,
+            app_root: [3; 32],
+            applied_txs_root: Sha2Hash([4; 32]),
+            prev_hash: Sha2Hash([5; 32]),
+        };
+
+        let decoded = MockHeader::decode_from_slice(&header.encode_to_vec()).unwrap();
+        assert_ne!(decoded, header);
+        assert_eq!(
+            decoded.sequencers_root,
+            ConsensusParticipantRoot::Registered(root)
+        );
+        assert_eq!(decoded.hash(), header.hash());
+    }
+
+    #[test]
+    fn test_unsupported_version_is_rejected() {
+        let header = MockHeader {
+            da_blockhash: MockHash([1; 32]),
+            sequencers_root: ConsensusParticipantRoot::Anyone,
+            provers_root: ConsensusParticipantRoot::Centralized(MockAddress([2; 32])),
+            app_root: [3; 32],
+            applied_txs_root: Sha2Hash([4; 32]),
+            prev_hash: Sha2Hash([5; 32]),
+        };
+        let mut encoded = header.encode_to_vec();
+        encoded[0] = ROLLUP_HEADER_VERSION + 1;
+
+        assert!(matches!(
+            MockHeader::decode_from_slice(&encoded),
+            Err(RollupHeaderDecodeError::UnsupportedVersion(v)) if v == ROLLUP_HEADER_VERSION + 1
+        ));
+    }
+}
+
+#[cfg(test)]
+mod consensus_set_tests {
+    use super::*;
+    use crate::{
+        core::mocks::MockAddress,
+        stf::{ConsensusRole, OpaqueAddress},
+    };
+
+    fn update(addr: u8, new_role: Option<ConsensusRole>) -> ConsensusSetUpdate<OpaqueAddress> {
+        ConsensusSetUpdate {
+            address: Rc::new(vec![addr; 32]),
+            new_role,
+        }
+    }
+
+    #[test]
+    fn test_registered_set_updates() {
+        let mut root = ConsensusParticipantRoot::RegisteredSet(RegisteredParticipants::new(vec![
+            (MockAddress([1; 32]), ConsensusRole::Sequencer),
+            (MockAddress([2; 32]), ConsensusRole::Sequencer),
+        ]));
+
+        root.process_updates(vec![
+            update(3, Some(ConsensusRole::Sequencer)),
+            update(2, Some(ConsensusRole::ProverAndSequencer)),
+            // Slashing removes the participant.
+            update(1, None),
+            // Invalid addresses are ignored.
+            ConsensusSetUpdate {
+                address: Rc::new(vec![4; 3]),
+                new_role: Some(ConsensusRole::Sequencer),
+            },
+        ])
+        .unwrap();
+
+        assert!(!root.allows(MockAddress([1; 32])).unwrap());
+        assert!(root.allows(MockAddress([2; 32])).unwrap());
+        assert!(root.allows(MockAddress([3; 32])).unwrap());
+
+        root.finalize();
+        let expected = RegisteredParticipants::new(vec![
+            (MockAddress([3; 32]), ConsensusRole::Sequencer),
+            (MockAddress([2; 32]), ConsensusRole::ProverAndSequencer),
+        ]);
+        assert_eq!(root, ConsensusParticipantRoot::Registered(expected.root()));
+    }
+
+    #[test]
+    fn test_root_commits_to_roles() {
+        let set = |role| RegisteredParticipants::new(vec![(MockAddress([1; 32]), role)]);
+        assert_ne!(
+            set(ConsensusRole::Sequencer).root(),
+            set(ConsensusRole::ProverAndSequencer).root()
+        );
+    }
+
+    #[test]
+    fn test_load_participants() {
+        let participants = vec![
+            (MockAddress([1; 32]), ConsensusRole::Sequencer),
+            (MockAddress([2; 32]), ConsensusRole::Sequencer),
+        ];
+        let root = RegisteredParticipants::new(participants.clone()).root();
+
+        let mut registered = ConsensusParticipantRoot::Registered(root.clone());
+        assert_eq!(
+            registered.allows(MockAddress([1; 32])),
+            Err(ConsensusSetError::ParticipantsNotLoaded)
+        );
+        assert_eq!(
+            registered.process_update(update(1, None)),
+            Err(ConsensusSetError::ParticipantsNotLoaded)
+        );
+        assert!(registered
+            .load_participants(vec![(MockAddress([1; 32]), ConsensusRole::Sequencer)])
+            .is_err());
+        // The roles are part of the root.
+        assert!(registered
+            .load_participants(vec![
+                (MockAddress([1; 32]), ConsensusRole::Prover),
+                (MockAddress([2; 32]), ConsensusRole::Sequencer),
+            ])
+            .is_err());
+        registered.load_participants(participants).unwrap();
+        assert!(registered.allows(MockAddress([2; 32])).unwrap());
+        assert!(!registered.allows(MockAddress([3; 32])).unwrap());
+
+        // Loading and finalizing without changes keeps the root.
+        registered
+            .process_updates(vec![
+                update(1, Some(ConsensusRole::Sequencer)),
+                update(3, None),
+            ])
+            .unwrap();
+        registered.finalize();
+        assert_eq!(registered, ConsensusParticipantRoot::Registered(root));
+    }
+
+    #[test]
+    fn test_centralized_set_becomes_registered() {
+        let centralized = ConsensusParticipantRoot::Centralized(MockAddress([1; 32]));
+
+        // Updates which don't change the participants keep the set centralized.
+        let mut root = centralized.clone();
+        root.process_updates(vec![
+            update(1, Some(ConsensusRole::Sequencer)),
+            update(2, None),
+        ])
+        .unwrap();
+        root.finalize();
+        assert_eq!(root, centralized);
+
+        root.process_update(update(2, Some(ConsensusRole::Sequencer)))
+            .unwrap();
+        assert!(root.allows(MockAddress([1; 32])).unwrap());
+        assert!(root.allows(MockAddress([2; 32])).unwrap());
+        let expected = RegisteredParticipants::new(vec![
+            (MockAddress([1; 32]), ConsensusRole::Sequencer),
+            (MockAddress([2; 32]), ConsensusRole::Sequencer),
+        ]);
+        assert_eq!(root, ConsensusParticipantRoot::RegisteredSet(expected));
+
+        // Slashing the centralized participant leaves nobody.
+        let mut root = centralized;
+        root.process_update(update(1, None)).unwrap();
+        assert!(!root.allows(MockAddress([1; 32])).unwrap());
+
+        let mut anyone = ConsensusParticipantRoot::<MockAddress>::Anyone;
+        anyone.process_update(update(1, None)).unwrap();
+        assert!(anyone.allows(MockAddress([1; 32])).unwrap());
+    }
+
+    #[test]
+    fn test_update_for_role() {
+        let prover = update(1, Some(ConsensusRole::Prover));
+        assert_eq!(
+            prover.for_role(ConsensusRole::Prover).new_role,
+            Some(ConsensusRole::Prover)
+        );
+        assert_eq!(prover.for_role(ConsensusRole::Sequencer).new_role, None);
+
+        let both = update(1, Some(ConsensusRole::ProverAndSequencer));
+        assert_eq!(
+            both.for_role(ConsensusRole::Sequencer).new_role,
+            Some(ConsensusRole::Sequencer)
+        );
+        assert_eq!(
+            update(1, None).for_role(ConsensusRole::Prover).new_role,
+            None
+        );
+    }
+}
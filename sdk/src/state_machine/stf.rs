@@ -27,15 +27,16 @@ pub trait StateTransitionFunction {
 
     /// Called at the beginning of each DA-layer block - whether or not that block contains any
     /// data relevant to the rollup.
-    fn begin_slot(&mut self);
+    fn begin_slot(&mut self, slot: SlotInfo);
 
-    /// Apply a batch of transactions to the rollup, slashing the sequencer who proposed the batch on failure
+    /// Apply a batch of transactions to the rollup, slashing the sequencer who proposed the batch on failure.
+    /// Returns a receipt for every transaction of the batch, in order.
     fn apply_batch(
         &mut self,
         batch: Self::Batch,
         sequencer: &[u8],
         misbehavior_hint: Option<Self::MisbehaviorProof>,
-    ) -> Result<Vec<Vec<Event>>, ConsensusSetUpdate<OpaqueAddress>>;
+    ) -> Result<BatchReceipt, ConsensusSetUpdate<OpaqueAddress>>;
 
     fn apply_proof(
         &self,
@@ -48,41 +49,157 @@ pub trait StateTransitionFunction {
     fn end_slot(&mut self) -> (Self::StateRoot, Vec<ConsensusSetUpdate<OpaqueAddress>>);
 }
 
-#[derive(Debug, Clone, Copy, BorshSerialize, BorshDeserialize)]
+/// Describes the DA layer block which is being processed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct SlotInfo {
+    pub da_height: u64,
+    /// The sha2 hash of the encoded hash of the DA layer block, which has the same size on every DA layer.
+    pub da_hash: [u8; 32],
+    /// The timestamp of the DA layer block, in seconds since the unix epoch.
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
 pub enum ConsensusRole {
     Prover,
     Sequencer,
     ProverAndSequencer,
 }
 
+impl ConsensusRole {
+    /// Returns true if a participant with this role also has the given role.
+    pub fn includes(self, role: ConsensusRole) -> bool {
+        self == role || self == ConsensusRole::ProverAndSequencer
+    }
+}
+
 /// A key-value pair representing a change to the rollup state
 #[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
 pub struct Event {
     pub key: EventKey,
     pub value: EventValue,
+    /// The module which emitted the event. None if the event wasn't emitted by a module.
+    pub module: Option<EventModule>,
 }
 
 impl Event {
     pub fn new(key: &str, value: &str) -> Self {
+        Self::from_bytes(key.as_bytes().to_vec(), value.as_bytes().to_vec())
+    }
+
+    /// Creates an event from an arbitrary key and value, e.g. a serialized payload.
+    pub fn from_bytes(key: Vec<u8>, value: Vec<u8>) -> Self {
+        Self {
+            key: EventKey(Rc::new(key)),
+            value: EventValue(Rc::new(value)),
+            module: None,
+        }
+    }
+
+    /// Attributes the event to `module`.
+    pub fn with_module(self, module: EventModule) -> Self {
         Self {
-            key: EventKey(Rc::new(key.as_bytes().to_vec())),
-            value: EventValue(Rc::new(value.as_bytes().to_vec())),
+            module: Some(module),
+            ..self
         }
     }
 }
 
+/// Identifies the module which emitted an event.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, BorshSerialize, BorshDeserialize)]
+pub struct EventModule {
+    /// The name of the module in the runtime.
+    pub name: String,
+    pub address: Vec<u8>,
+}
+
+/// The outcome of a transaction. Mirrors the status stored by the ledger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum TxStatus {
+    /// The transaction was executed and its changes were applied.
+    Applied,
+    /// The transaction was not executed.
+    Skipped,
+    /// The transaction was executed, but failed and its changes were reverted.
+    Reverted,
+}
+
+/// The result of applying a single transaction.
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct TransactionReceipt {
+    pub tx_hash: [u8; 32],
+    /// The raw transaction, as it was posted by the sequencer.
+    pub body: Vec<u8>,
+    pub status: TxStatus,
+    /// The events emitted by the transaction. Empty unless the transaction was applied.
+    pub events: Vec<Event>,
+    /// The reason why the transaction was skipped or reverted.
+    pub error: Option<TxError>,
+    pub gas_used: u64,
+}
+
+/// Describes why a transaction was skipped or reverted.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct TxError {
+    /// The module which raised the error, None if the transaction was rejected before reaching a module.
+    /// The crate name of the module for a coded error, else the name of the module the transaction was dispatched to.
+    pub module: Option<String>,
+    /// Identifies the error within its module. Zero if the module didn't assign a code to the error.
+    pub code: u32,
+    pub message: String,
+}
+
+/// The result of applying a batch of transactions.
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct BatchReceipt {
+    pub batch_hash: [u8; 32],
+    pub tx_receipts: Vec<TransactionReceipt>,
+}
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, BorshSerialize, BorshDeserialize, Clone)]
 pub struct EventKey(Rc<Vec<u8>>);
 
+impl AsRef<[u8]> for EventKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for EventKey {
+    fn from(key: Vec<u8>) -> Self {
+        Self(Rc::new(key))
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
 pub struct EventValue(Rc<Vec<u8>>);
 
+impl AsRef<[u8]> for EventValue {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 #[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
 pub struct ConsensusSetUpdate<Address> {
     pub address: Address,
     pub new_role: Option<ConsensusRole>,
 }
 
+impl<Address: Clone> ConsensusSetUpdate<Address> {
+    /// Restricts the update to the set of participants with the given `role`: the address
+    /// keeps the role if its new role includes it, and is removed from the set otherwise.
+    pub fn for_role(&self, role: ConsensusRole) -> Self {
+        ConsensusSetUpdate {
+            address: self.address.clone(),
+            new_role: self
+                .new_role
+                .filter(|new_role| new_role.includes(role))
+                .map(|_| role),
+        }
+    }
+}
+
 impl ConsensusSetUpdate<OpaqueAddress> {
     pub fn slashing(sequencer: &[u8]) -> ConsensusSetUpdate<OpaqueAddress> {
         let faulty_sequencer = Rc::new(sequencer.to_vec());
@@ -1,2 +1,223 @@
-// TODO: @preston-evans98: build a tx_trie from a simple binary merkle tree
-// https://github.com/Sovereign-Labs/nmt-rs/tree/master/src/simple_merkle
+//! A simple binary merkle tree over transaction hashes, following the layout of RFC 6962:
+//! the leaves are split at the largest power of two smaller than their number, and leaves
+//! and inner nodes are hashed with different prefixes so that one can't be passed off as the other.
+use borsh::{BorshDeserialize, BorshSerialize};
+use sha2::{Digest, Sha256};
+
+use crate::core::crypto::hash::{sha2, DefaultHash, Sha2Hash};
+
+const LEAF_PREFIX: u8 = 0;
+const INNER_PREFIX: u8 = 1;
+
+/// A merkle tree over the hashes of the transactions of a batch, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxTrie {
+    leaves: Vec<DefaultHash>,
+}
+
+/// Proves that a transaction is included at a given position of a [`TxTrie`].
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct InclusionProof {
+    /// The position of the transaction in the trie.
+    pub index: u64,
+    /// The number of transactions in the trie.
+    pub num_leaves: u64,
+    /// The hashes of the sibling subtrees, ordered from the leaf to the root.
+    pub siblings: Vec<DefaultHash>,
+}
+
+impl TxTrie {
+    /// Builds the trie from the hashes of the transactions.
+    pub fn new<H: AsRef<[u8]>>(tx_hashes: impl IntoIterator<Item = H>) -> Self {
+        Self {
+            leaves: tx_hashes
+                .into_iter()
+                .map(|tx_hash| leaf_hash(tx_hash.as_ref()))
+                .collect(),
+        }
+    }
+
+    /// Returns the number of transactions in the trie.
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Returns true if the trie contains no transactions.
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Returns the root of the trie. The root of an empty trie is the hash of the empty string.
+    pub fn root(&self) -> DefaultHash {
+        subtree_root(&self.leaves)
+    }
+
+    /// Returns a proof that the transaction at `index` is included in the trie,
+    /// or None if the index is out of bounds.
+    pub fn prove(&self, index: usize) -> Option<InclusionProof> {
+        if index >= self.leaves.len() {
+            return None;
+        }
+
+        let mut siblings = Vec::new();
+        collect_siblings(&self.leaves, index, &mut siblings);
+
+        Some(InclusionProof {
+            index: index as u64,
+            num_leaves: self.leaves.len() as u64,
+            siblings,
+        })
+    }
+}
+
+impl InclusionProof {
+    /// Checks that the transaction with hash `tx_hash` is included in the trie with the given root
+    /// at the position claimed by the proof.
+    pub fn verify(&self, root: &DefaultHash, tx_hash: impl AsRef<[u8]>) -> bool {
+        if self.index >= self.num_leaves {
+            return false;
+        }
+
+        let leaf = leaf_hash(tx_hash.as_ref());
+        match root_from_siblings(self.index, self.num_leaves, leaf, &self.siblings) {
+            Some(computed_root) => &computed_root == root,
+            None => false,
+        }
+    }
+}
+
+fn leaf_hash(tx_hash: &[u8]) -> DefaultHash {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(tx_hash);
+    Sha2Hash(hasher.finalize().into())
+}
+
+fn inner_hash(left: &DefaultHash, right: &DefaultHash) -> DefaultHash {
+    let mut hasher = Sha256::new();
+    hasher.update([INNER_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    Sha2Hash(hasher.finalize().into())
+}
+
+/// Returns the largest power of two strictly smaller than `n`. `n` must be greater than 1.
+fn split_point(n: u64) -> u64 {
+    debug_assert!(n > 1);
+    1 << (63 - (n - 1).leading_zeros())
+}
+
+fn subtree_root(leaves: &[DefaultHash]) -> DefaultHash {
+    match leaves.len() {
+        0 => sha2(&[]),
+        1 => leaves[0].clone(),
+        n => {
+            let k = split_point(n as u64) as usize;
+            inner_hash(&subtree_root(&leaves[..k]), &subtree_root(&leaves[k..]))
+        }
+    }
+}
+
+fn collect_siblings(leaves: &[DefaultHash], index: usize, siblings: &mut Vec<DefaultHash>) {
+    if leaves.len() <= 1 {
+        return;
+    }
+
+    let k = split_point(leaves.len() as u64) as usize;
+    if index < k {
+        collect_siblings(&leaves[..k], index, siblings);
+        siblings.push(subtree_root(&leaves[k..]));
+    } else {
+        collect_siblings(&leaves[k..], index - k, siblings);
+        siblings.push(subtree_root(&leaves[..k]));
+    }
+}
+
+/// Recomputes the root of a subtree with `num_leaves` leaves from the leaf at `index` and the
+/// siblings on its path. Returns None if the number of siblings doesn't match the shape of the tree.
+fn root_from_siblings(
+    index: u64,
+    num_leaves: u64,
+    leaf: DefaultHash,
+    siblings: &[DefaultHash],
+) -> Option<DefaultHash> {
+    if num_leaves == 1 {
+        return siblings.is_empty().then_some(leaf);
+    }
+
+    let (sibling, rest) = siblings.split_last()?;
+    let k = split_point(num_leaves);
+    if index < k {
+        let left = root_from_siblings(index, k, leaf, rest)?;
+        Some(inner_hash(&left, sibling))
+    } else {
+        let right = root_from_siblings(index - k, num_leaves - k, leaf, rest)?;
+        Some(inner_hash(sibling, &right))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx_hashes(n: u8) -> Vec<[u8; 32]> {
+        (0..n).map(|i| sha2(&[i]).0).collect()
+    }
+
+    #[test]
+    fn test_root_of_small_tries() {
+        let hashes = tx_hashes(3);
+        let leaves: Vec<_> = hashes.iter().map(|h| leaf_hash(h)).collect();
+
+        assert_eq!(TxTrie::new(Vec::<[u8; 32]>::new()).root(), sha2(&[]));
+        assert_eq!(TxTrie::new(&hashes[..1]).root(), leaves[0]);
+        assert_eq!(
+            TxTrie::new(&hashes[..2]).root(),
+            inner_hash(&leaves[0], &leaves[1])
+        );
+        assert_eq!(
+            TxTrie::new(&hashes).root(),
+            inner_hash(&inner_hash(&leaves[0], &leaves[1]), &leaves[2])
+        );
+    }
+
+    #[test]
+    fn test_inclusion_proofs() {
+        for n in 1..=17 {
+            let hashes = tx_hashes(n);
+            let trie = TxTrie::new(&hashes);
+            let root = trie.root();
+
+            for (index, tx_hash) in hashes.iter().enumerate() {
+                let proof = trie.prove(index).unwrap();
+                assert!(proof.verify(&root, tx_hash));
+
+                // The proof doesn't hold for another transaction or another position.
+                let other = (index + 1) % hashes.len();
+                if other != index {
+                    assert!(!proof.verify(&root, hashes[other]));
+                    let moved = InclusionProof {
+                        index: other as u64,
+                        ..proof.clone()
+                    };
+                    assert!(!moved.verify(&root, tx_hash));
+                }
+            }
+            assert!(trie.prove(hashes.len()).is_none());
+        }
+    }
+
+    #[test]
+    fn test_malformed_proof_is_rejected() {
+        let hashes = tx_hashes(5);
+        let trie = TxTrie::new(&hashes);
+        let root = trie.root();
+        let mut proof = trie.prove(4).unwrap();
+
+        proof.siblings.push(root.clone());
+        assert!(!proof.verify(&root, hashes[4]));
+
+        proof.siblings.clear();
+        assert!(!proof.verify(&root, hashes[4]));
+    }
+}
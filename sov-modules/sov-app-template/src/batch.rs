@@ -1,12 +1,34 @@
 use crate::tx_verifier::RawTx;
 use borsh::{BorshDeserialize, BorshSerialize};
-use sovereign_sdk::core::traits::{BatchTrait, CanonicalHash, TransactionTrait};
+use sov_modules_api::{Context, Hasher};
+use sovereign_sdk::core::traits::{BatchTrait, TransactionTrait};
 
 #[derive(Debug, PartialEq, BorshDeserialize, BorshSerialize, Clone)]
 pub struct Batch {
     pub txs: Vec<RawTx>,
 }
 
+impl Batch {
+    /// Returns the hashes of the transactions in the batch, in order.
+    pub fn tx_hashes<C: Context>(&self) -> Vec<[u8; 32]> {
+        self.txs.iter().map(RawTx::hash::<C>).collect()
+    }
+
+    /// Hashes the batch, committing to the hashes of its transactions and their order.
+    pub fn hash<C: Context>(&self) -> [u8; 32] {
+        batch_hash::<C>(&self.tx_hashes::<C>())
+    }
+}
+
+/// Computes the hash of a batch from the hashes of its transactions.
+pub(crate) fn batch_hash<C: Context>(tx_hashes: &[[u8; 32]]) -> [u8; 32] {
+    let mut hasher = C::Hasher::new();
+    for tx_hash in tx_hashes {
+        hasher.update(tx_hash);
+    }
+    hasher.finalize()
+}
+
 impl BatchTrait for Batch {
     type Transaction = RawTx;
 
@@ -19,14 +41,13 @@ impl BatchTrait for Batch {
     }
 }
 
-impl TransactionTrait for RawTx {
-    type Hash = [u8; 32];
+impl RawTx {
+    /// Hashes the serialized transaction with the `Hasher` of the given `Context`.
+    pub fn hash<C: Context>(&self) -> [u8; 32] {
+        C::Hasher::hash(&self.data)
+    }
 }
 
-impl CanonicalHash for RawTx {
-    type Output = [u8; 32];
-
-    fn hash(&self) -> Self::Output {
-        todo!()
-    }
+impl TransactionTrait for RawTx {
+    type Hash = [u8; 32];
 }
@@ -7,20 +7,36 @@ pub use tx_hooks::TxHooks;
 pub use tx_hooks::VerifiedTx;
 pub use tx_verifier::{RawTx, TxVerifier};
 
-use sov_modules_api::{Context, DispatchCall, Genesis};
-use sov_state::{Storage, WorkingSet};
+use std::collections::HashSet;
+
+use batch::batch_hash;
+use sov_modules_api::{Context, DispatchCall, Genesis, SlotHooks, TxMetadata};
+use sov_state::{GasCosts, GasMeter, OutOfGas, Storage, WorkingSet};
 use sovereign_sdk::{
     core::{mocks::MockProof, traits::BatchTrait},
     jmt,
-    stf::{ConsensusSetUpdate, OpaqueAddress, StateTransitionFunction},
+    stf::{
+        BatchReceipt, ConsensusSetUpdate, Event, OpaqueAddress, SlotInfo, StateTransitionFunction,
+        TransactionReceipt, TxError, TxStatus,
+    },
 };
 
 pub struct AppTemplate<C: Context, V, RT, H> {
     pub current_storage: C::Storage,
     pub runtime: RT,
+    /// The gas charged for the state accesses of the transactions.
+    pub gas_costs: GasCosts,
     tx_verifier: V,
     tx_hooks: H,
     working_set: Option<WorkingSet<C::Storage>>,
+    /// The DA layer block which is being processed.
+    current_slot: Option<SlotInfo>,
+    /// Hashes of the transactions processed in the current slot, used to skip duplicates.
+    /// They are kept in memory for the current slot only: a transaction replayed in a later slot
+    /// isn't detected here, it is the nonce check of the tx hooks which rejects it.
+    slot_tx_hashes: HashSet<[u8; 32]>,
+    /// The events of the slot hooks which failed in the current slot.
+    slot_events: Vec<Event>,
 }
 
 impl<C: Context, V, RT, H> AppTemplate<C, V, RT, H> {
@@ -28,22 +44,166 @@ impl<C: Context, V, RT, H> AppTemplate<C, V, RT, H> {
         Self {
             runtime,
             current_storage: storage,
+            gas_costs: GasCosts::default(),
             tx_verifier,
             tx_hooks,
             working_set: None,
+            current_slot: None,
+            slot_tx_hashes: HashSet::new(),
+            slot_events: Vec::new(),
+        }
+    }
+
+    /// Returns the events reporting the slot hooks which failed in the current, or last, slot.
+    /// The events of the begin hooks come first, they are cleared when the next slot begins.
+    pub fn slot_events(&self) -> &[Event] {
+        &self.slot_events
+    }
+}
+
+impl<C: Context, V, RT, H> AppTemplate<C, V, RT, H>
+where
+    RT: DispatchCall<Context = C>,
+    V: TxVerifier,
+    H: TxHooks<Context = C, Transaction = <V as TxVerifier>::Transaction>,
+{
+    /// Applies the transactions of a batch, returns the slashing of the sequencer if the batch is invalid,
+    /// i.e. if it contains a tx rejected by the pre-dispatch hook or a message which can't be decoded.
+    fn apply_txs(
+        &mut self,
+        txs: Vec<V::Transaction>,
+        tx_hashes: Vec<[u8; 32]>,
+        tx_bodies: Vec<Vec<u8>>,
+        slot: &SlotInfo,
+        sequencer: &[u8],
+        batch_tx_hashes: &mut HashSet<[u8; 32]>,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Result<Vec<TransactionReceipt>, ConsensusSetUpdate<OpaqueAddress>> {
+        let mut tx_receipts = Vec::with_capacity(txs.len());
+
+        for ((tx, tx_hash), body) in txs.into_iter().zip(tx_hashes).zip(tx_bodies) {
+            // A transaction which was already processed in this slot is skipped without being executed.
+            // Replays from earlier slots are left to the nonce check of the pre-dispatch hook.
+            if self.slot_tx_hashes.contains(&tx_hash) || !batch_tx_hashes.insert(tx_hash) {
+                tx_receipts.push(TransactionReceipt {
+                    tx_hash,
+                    body,
+                    status: TxStatus::Skipped,
+                    events: Vec::new(),
+                    error: Some(TxError {
+                        module: None,
+                        code: 0,
+                        message: "Duplicate transaction".to_owned(),
+                    }),
+                    gas_used: 0,
+                });
+                continue;
+            }
+
+            // Every tx is metered on its own, hooks included.
+            let batch_gas_meter =
+                working_set.set_gas_meter(GasMeter::new(u64::MAX, self.gas_costs.clone()));
+            let receipt = self.apply_tx(tx, tx_hash, body, slot, sequencer, working_set);
+            working_set.set_gas_meter(batch_gas_meter);
+            tx_receipts.push(receipt?);
         }
+        Ok(tx_receipts)
+    }
+
+    /// Applies a transaction with the gas meter of `working_set`, which has no limit until the pre-dispatch hook
+    /// returns the limit of the tx. Returns the slashing of the sequencer if the tx is invalid.
+    ///
+    /// Nothing charges a fee for the gas used yet, it is only reported in the receipt.
+    fn apply_tx(
+        &mut self,
+        tx: V::Transaction,
+        tx_hash: [u8; 32],
+        body: Vec<u8>,
+        slot: &SlotInfo,
+        sequencer: &[u8],
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Result<TransactionReceipt, ConsensusSetUpdate<OpaqueAddress>> {
+        // Run the stateful verification, possibly modifies the state.
+        let verified_tx = self
+            .tx_hooks
+            .pre_dispatch_tx_hook(tx, working_set)
+            .or(Err(ConsensusSetUpdate::slashing(sequencer)))?;
+
+        // If the serialization is invalid, the sequencer is malicious. Slash them.
+        let msg = RT::decode_call(verified_tx.runtime_message())
+            .or(Err(ConsensusSetUpdate::slashing(sequencer)))?;
+        let ctx = C::with_metadata(
+            verified_tx.sender().clone(),
+            TxMetadata {
+                slot: slot.clone(),
+                tx_hash,
+                sequencer: sequencer.to_vec(),
+            },
+        );
+        let module_name = self.runtime.module_name(&msg);
+
+        // The gas used by the pre-dispatch hook counts towards the limit of the tx.
+        let gas_limit = verified_tx.gas_limit();
+        let tx_gas_meter = working_set.gas_meter().clone().with_limit(gas_limit);
+        working_set.set_gas_meter(tx_gas_meter);
+        // The call runs in its own checkpoint, so a failing call can be reverted without
+        // losing the changes made by the tx hooks (nonce bump, fees).
+        let tx_result = working_set.with_checkpoint(|working_set| {
+            let out_of_gas = |e: OutOfGas| TxError {
+                module: None,
+                code: 0,
+                message: e.to_string(),
+            };
+            working_set.charge_call().map_err(out_of_gas)?;
+            let result = self.runtime.dispatch_call(msg, working_set, &ctx);
+            // The state accesses stop once the gas runs out, so the result of the call can't be trusted.
+            working_set.gas_meter().check().map_err(out_of_gas)?;
+            // A coded error names the module which raised it, possibly one called by the dispatched module.
+            result.map_err(|e| TxError {
+                module: Some(e.module().unwrap_or(module_name).to_owned()),
+                code: e.code(),
+                message: e.to_string(),
+            })
+        });
+
+        // The post-dispatch hook can't be reverted, so it runs to completion: its gas is counted
+        // but not limited, and the tx is charged at most its limit.
+        let tx_gas_meter = working_set.gas_meter().clone().with_limit(u64::MAX);
+        working_set.set_gas_meter(tx_gas_meter);
+        self.tx_hooks
+            .post_dispatch_tx_hook(verified_tx, working_set);
+        let gas_used = working_set.gas_meter().used().total().min(gas_limit);
+
+        Ok(match tx_result {
+            Ok(resp) => TransactionReceipt {
+                tx_hash,
+                body,
+                status: TxStatus::Applied,
+                events: resp.events,
+                error: None,
+                gas_used,
+            },
+            Err(error) => TransactionReceipt {
+                tx_hash,
+                body,
+                status: TxStatus::Reverted,
+                events: Vec::new(),
+                error: Some(error),
+                gas_used,
+            },
+        })
     }
 }
 
 impl<C: Context, V, RT, H> StateTransitionFunction for AppTemplate<C, V, RT, H>
 where
-    RT: DispatchCall<Context = C> + Genesis<Context = C>,
+    RT: DispatchCall<Context = C> + Genesis<Context = C> + SlotHooks<Context = C>,
     V: TxVerifier,
     H: TxHooks<Context = C, Transaction = <V as TxVerifier>::Transaction>,
 {
     type StateRoot = jmt::RootHash;
 
-    type ChainParams = ();
+    type ChainParams = <RT as Genesis>::Config;
 
     type Transaction = RawTx;
 
@@ -53,10 +213,10 @@ where
 
     type MisbehaviorProof = ();
 
-    fn init_chain(&mut self, _params: Self::ChainParams) {
+    fn init_chain(&mut self, params: Self::ChainParams) {
         let working_set = &mut WorkingSet::new(self.current_storage.clone());
         self.runtime
-            .genesis(working_set)
+            .genesis(&params, working_set)
             .expect("module initialization must succeed");
         let (log, witness) = working_set.freeze();
         self.current_storage
@@ -64,8 +224,12 @@ where
             .expect("Storage update must succeed");
     }
 
-    fn begin_slot(&mut self) {
-        self.working_set = Some(WorkingSet::new(self.current_storage.clone()));
+    fn begin_slot(&mut self, slot: SlotInfo) {
+        let mut working_set = WorkingSet::new(self.current_storage.clone());
+        self.slot_events = self.runtime.begin_slot_hooks(&slot, &mut working_set);
+        self.working_set = Some(working_set);
+        self.current_slot = Some(slot);
+        self.slot_tx_hashes.clear();
     }
 
     fn apply_batch(
@@ -73,54 +237,49 @@ where
         batch: Self::Batch,
         sequencer: &[u8],
         _misbehavior_hint: Option<Self::MisbehaviorProof>,
-    ) -> Result<
-        Vec<Vec<sovereign_sdk::stf::Event>>,
-        sovereign_sdk::stf::ConsensusSetUpdate<OpaqueAddress>,
-    > {
-        let mut events = Vec::new();
+    ) -> Result<BatchReceipt, ConsensusSetUpdate<OpaqueAddress>> {
+        let raw_txs = batch.take_transactions();
+        let tx_hashes: Vec<[u8; 32]> = raw_txs.iter().map(RawTx::hash::<C>).collect();
+        let batch_hash = batch_hash::<C>(&tx_hashes);
+        // The verification consumes the raw transactions, their bodies are kept for the receipts.
+        let tx_bodies = raw_txs.iter().map(|tx| tx.data.clone()).collect();
 
         // Run the stateless verification.
         let txs = self
             .tx_verifier
-            .verify_txs_stateless(batch.take_transactions())
+            .verify_txs_stateless(raw_txs)
             .or(Err(ConsensusSetUpdate::slashing(sequencer)))?;
-        let mut batch_workspace = WorkingSet::new(self.current_storage.clone());
-
-        for tx in txs {
-            batch_workspace = batch_workspace.to_revertable();
-            // Run the stateful verification, possibly modifies the state.
-            let verified_tx = self
-                .tx_hooks
-                .pre_dispatch_tx_hook(tx, &mut batch_workspace)
-                .or(Err(ConsensusSetUpdate::slashing(sequencer)))?;
-
-            if let Ok(msg) = RT::decode_call(verified_tx.runtime_message()) {
-                let ctx = C::new(verified_tx.sender().clone());
-                let tx_result = self.runtime.dispatch_call(msg, &mut batch_workspace, &ctx);
-
-                self.tx_hooks
-                    .post_dispatch_tx_hook(verified_tx, &mut batch_workspace);
-
-                match tx_result {
-                    Ok(resp) => {
-                        events.push(resp.events);
-                        batch_workspace = batch_workspace.commit();
-                    }
-                    Err(e) => {
-                        // Don't merge the tx workspace. TODO add tests for this scenario
-                        batch_workspace.revert();
-                        panic!("Demo app txs must succeed but failed with err: {}", e)
-                    }
-                }
-            } else {
-                // If the serialization is invalid, the sequencer is malicious. Slash them.
-                batch_workspace.revert();
-                return Err(ConsensusSetUpdate::slashing(sequencer));
-            }
-        }
-        self.working_set = Some(batch_workspace);
 
-        Ok(events)
+        // The batch is applied on top of the changes of the slot, and discarded as a whole
+        // if the sequencer gets slashed.
+        let mut working_set = self
+            .working_set
+            .take()
+            .expect("begin_slot must be called before apply_batch");
+        let slot = self
+            .current_slot
+            .clone()
+            .expect("begin_slot must be called before apply_batch");
+        let mut batch_tx_hashes = HashSet::with_capacity(txs.len());
+        let result = working_set.with_checkpoint(|working_set| {
+            self.apply_txs(
+                txs,
+                tx_hashes,
+                tx_bodies,
+                &slot,
+                sequencer,
+                &mut batch_tx_hashes,
+                working_set,
+            )
+        });
+        self.working_set = Some(working_set);
+        let tx_receipts = result?;
+        self.slot_tx_hashes.extend(batch_tx_hashes);
+
+        Ok(BatchReceipt {
+            batch_hash,
+            tx_receipts,
+        })
     }
 
     fn apply_proof(
@@ -137,7 +296,12 @@ where
         Self::StateRoot,
         Vec<sovereign_sdk::stf::ConsensusSetUpdate<OpaqueAddress>>,
     ) {
-        let (cache_log, witness) = self.working_set.take().unwrap().freeze();
+        let mut working_set = self.working_set.take().unwrap();
+        let slot = self.current_slot.take().unwrap();
+        let events = self.runtime.end_slot_hooks(&slot, &mut working_set);
+        self.slot_events.extend(events);
+
+        let (cache_log, witness) = working_set.freeze();
         let root_hash = self
             .current_storage
             .validate_and_commit(cache_log, &witness)
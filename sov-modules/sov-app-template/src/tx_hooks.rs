@@ -6,9 +6,13 @@ pub trait VerifiedTx {
     type Address;
     fn sender(&self) -> &Self::Address;
     fn runtime_message(&self) -> &[u8];
+    /// The maximum gas the transaction can use, see [`sov_state::GasMeter`]. It covers the tx hooks as well
+    /// as the runtime message, though the post-dispatch hook always runs to completion.
+    fn gas_limit(&self) -> u64;
 }
 
 /// TxHooks allows injecting custom logic into a transaction processing pipeline.
+/// The hooks are metered with the gas of the transaction, see [`VerifiedTx::gas_limit`].
 pub trait TxHooks {
     type Context: Context;
     type Transaction;
@@ -1,17 +1,45 @@
-use crate::{CallResponse, Context, Error, QueryResponse, Spec};
+use crate::{CallResponse, Context, Error, QueryResponse, SlotInfo, Spec};
 use sov_state::WorkingSet;
+use sovereign_sdk::stf::Event;
 
 /// Methods from this trait should be called only once during the rollup deployment.
 pub trait Genesis {
     type Context: Context;
 
+    /// Initial configuration for the genesis method.
+    type Config;
+
     /// Initializes the state of the rollup.
     fn genesis(
         &self,
+        config: &Self::Config,
         working_set: &mut WorkingSet<<<Self as Genesis>::Context as Spec>::Storage>,
     ) -> Result<(), Error>;
 }
 
+/// Runs the slot hooks of the modules of a runtime, in the order of the runtime fields.
+///
+/// The hook of every module runs in its own checkpoint: a failing hook is reverted
+/// without affecting the hooks of the other modules. The failures are returned as events,
+/// see [`CallResponse::slot_hook_failed`].
+pub trait SlotHooks {
+    type Context: Context;
+
+    /// Runs `Module::begin_slot_hook` of every module, returns an event for every failed hook.
+    fn begin_slot_hooks(
+        &self,
+        slot: &SlotInfo,
+        working_set: &mut WorkingSet<<<Self as SlotHooks>::Context as Spec>::Storage>,
+    ) -> Vec<Event>;
+
+    /// Runs `Module::end_slot_hook` of every module, returns an event for every failed hook.
+    fn end_slot_hooks(
+        &self,
+        slot: &SlotInfo,
+        working_set: &mut WorkingSet<<<Self as SlotHooks>::Context as Spec>::Storage>,
+    ) -> Vec<Event>;
+}
+
 /// A trait that needs to be implemented for any call message.
 pub trait DispatchCall {
     type Context: Context;
@@ -30,6 +58,9 @@ pub trait DispatchCall {
 
     /// Returns an address of the dispatched module.
     fn module_address(&self, message: &Self::Decodable) -> &<Self::Context as Spec>::Address;
+
+    /// Returns the name of the dispatched module, i.e. the name of the runtime field holding it.
+    fn module_name(&self, message: &Self::Decodable) -> &'static str;
 }
 
 /// A trait that needs to be implemented for any query message.
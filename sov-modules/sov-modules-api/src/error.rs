@@ -1,3 +1,5 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 use thiserror::Error;
 
@@ -6,5 +8,49 @@ use thiserror::Error;
 pub enum Error {
     /// Custom error thrown by a module.
     #[error(transparent)]
-    ModuleError(#[from] anyhow::Error),
+    ModuleError(anyhow::Error),
+    /// Error with a stable code thrown by a module, see [`CodedError`].
+    #[error(transparent)]
+    Coded(#[from] CodedError),
+}
+
+impl Error {
+    /// Returns the code of the error, zero if the module didn't assign a code to it.
+    pub fn code(&self) -> u32 {
+        match self {
+            Error::ModuleError(_) => 0,
+            Error::Coded(e) => e.code,
+        }
+    }
+
+    /// Returns the module which raised the error, if it has a code.
+    pub fn module(&self) -> Option<&str> {
+        match self {
+            Error::ModuleError(_) => None,
+            Error::Coded(e) => Some(&e.module),
+        }
+    }
+}
+
+/// Coded errors keep their code when they are propagated through `anyhow`.
+impl From<anyhow::Error> for Error {
+    fn from(e: anyhow::Error) -> Self {
+        match e.downcast::<CodedError>() {
+            Ok(coded) => Error::Coded(coded),
+            Err(e) => Error::ModuleError(e),
+        }
+    }
+}
+
+/// An error with a code identifying it within the module which raised it, so that users can match on
+/// the module and the code instead of the message. Usually created from a module error enum with `#[derive(ModuleError)]`.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Error, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+#[error("{message}")]
+pub struct CodedError {
+    /// The name of the crate of the module.
+    pub module: String,
+    pub code: u32,
+    pub message: String,
 }
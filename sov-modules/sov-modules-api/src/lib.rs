@@ -9,12 +9,12 @@ mod error;
 mod prefix;
 mod response;
 
-pub use dispatch::{DispatchCall, DispatchQuery, Genesis};
-pub use error::Error;
+pub use dispatch::{DispatchCall, DispatchQuery, Genesis, SlotHooks};
+pub use error::{CodedError, Error};
 pub use jmt::SimpleHasher as Hasher;
 
 pub use prefix::Prefix;
-pub use response::{CallResponse, QueryResponse};
+pub use response::{CallResponse, ModuleEvent, QueryResponse, SLOT_HOOK_FAILED_EVENT_KEY};
 
 use sov_state::{Storage, WorkingSet};
 use sovereign_sdk::{
@@ -23,6 +23,10 @@ use sovereign_sdk::{
 };
 
 pub use sovereign_sdk::core::traits::AddressTrait;
+pub use sovereign_sdk::stf::SlotInfo;
+
+// Used by the generated genesis configs, so that runtimes don't need to depend on serde.
+pub use serde;
 
 use std::fmt::Debug;
 
@@ -55,6 +59,21 @@ impl<'a> TryFrom<&'a [u8]> for Address {
     }
 }
 
+/// Addresses are serialized as hex strings, so they can be written by hand in configuration files.
+impl serde::Serialize for Address {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(self.addr))
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Address {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex_addr = String::deserialize(deserializer)?;
+        let addr = hex::decode(hex_addr).map_err(serde::de::Error::custom)?;
+        Self::try_from(&addr[..]).map_err(serde::de::Error::custom)
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum SigVerificationError {
     #[error("Bad signature")]
@@ -83,12 +102,18 @@ pub trait PublicKey {
 
 /// Spec contains types common for all modules.
 pub trait Spec {
-    type Address: AddressTrait + borsh::BorshDeserialize + borsh::BorshSerialize;
+    type Address: AddressTrait
+        + borsh::BorshDeserialize
+        + borsh::BorshSerialize
+        + serde::Serialize
+        + serde::de::DeserializeOwned;
 
     type Storage: Storage + Clone;
 
     type PublicKey: borsh::BorshDeserialize
         + borsh::BorshSerialize
+        + serde::Serialize
+        + serde::de::DeserializeOwned
         + Eq
         + TryFrom<&'static str>
         + Clone
@@ -107,13 +132,55 @@ pub trait Spec {
     type Witness: Witness;
 }
 
+/// Describes the transaction which is being executed and the DA layer block which contains it.
+/// It is part of the state transition, so it is the same in native and zk execution.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxMetadata {
+    pub slot: SlotInfo,
+    pub tx_hash: [u8; 32],
+    /// The DA layer address of the sequencer which submitted the batch.
+    pub sequencer: Vec<u8>,
+}
+
 /// Context contains functionality common for all modules.
 pub trait Context: Spec + Clone + Debug + PartialEq {
     /// Sender of the transaction.
     fn sender(&self) -> &Self::Address;
 
+    /// Metadata of the transaction.
+    fn metadata(&self) -> &TxMetadata;
+
     /// Constructor for the Context.
-    fn new(sender: Self::Address) -> Self;
+    fn with_metadata(sender: Self::Address, metadata: TxMetadata) -> Self;
+
+    /// Creates a context with empty metadata, for execution outside of a transaction.
+    fn new(sender: Self::Address) -> Self {
+        Self::with_metadata(sender, TxMetadata::default())
+    }
+
+    /// The height of the DA layer block which contains the transaction.
+    fn slot_number(&self) -> u64 {
+        self.metadata().slot.da_height
+    }
+
+    /// Identifies the DA layer block which contains the transaction, see [`SlotInfo::da_hash`].
+    fn da_hash(&self) -> &[u8; 32] {
+        &self.metadata().slot.da_hash
+    }
+
+    /// The timestamp of the DA layer block which contains the transaction, in seconds since the unix epoch.
+    fn timestamp(&self) -> u64 {
+        self.metadata().slot.timestamp
+    }
+
+    fn tx_hash(&self) -> &[u8; 32] {
+        &self.metadata().tx_hash
+    }
+
+    /// The DA layer address of the sequencer which submitted the transaction.
+    fn sequencer(&self) -> &[u8] {
+        &self.metadata().sequencer
+    }
 }
 
 /// Every module has to implement this trait.
@@ -131,9 +198,13 @@ pub trait Module {
     /// Module defined argument to the query method.
     type QueryMessage: Decode + Encode + Debug = NonInstantiable;
 
+    /// Configuration for the genesis method.
+    type Config = ();
+
     /// Genesis is called when a rollup is deployed and can be used to set initial state values in the module.
     fn genesis(
         &self,
+        _config: &Self::Config,
         _working_set: &mut WorkingSet<<Self::Context as Spec>::Storage>,
     ) -> Result<(), Error> {
         Ok(())
@@ -150,6 +221,24 @@ pub trait Module {
         unreachable!()
     }
 
+    /// Called at the beginning of each DA layer block, before its batches are applied.
+    fn begin_slot_hook(
+        &self,
+        _slot: &SlotInfo,
+        _working_set: &mut WorkingSet<<Self::Context as Spec>::Storage>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called at the end of each DA layer block, after all its batches were applied.
+    fn end_slot_hook(
+        &self,
+        _slot: &SlotInfo,
+        _working_set: &mut WorkingSet<<Self::Context as Spec>::Storage>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
     /// Query allows querying the module's state.
     fn query(
         &self,
@@ -169,4 +258,48 @@ pub trait ModuleInfo {
     // Returns an address for the module.
     // TODO: https://github.com/Sovereign-Labs/sovereign/issues/136
     fn address(&self) -> &<Self::Context as Spec>::Address;
+
+    /// Returns the prefix of the module. The prefixes of all the module state variables start with it.
+    fn prefix(&self) -> Prefix;
+
+    /// Calls another module with this module as the sender, by dispatching `msg` through `runtime`,
+    /// usually a struct deriving `DispatchCall` over the modules this one calls.
+    /// The metadata of the transaction is taken from `context`.
+    ///
+    /// The call follows the dispatch of a transaction: it is charged the base cost of a call and runs in its own
+    /// checkpoint. If it fails or runs out of gas, its changes are reverted and the error is returned, so the caller
+    /// can recover from it.
+    /// The events of the call are attributed to the module it was dispatched to, see [`DispatchCall::module_name`].
+    fn call_module<RT>(
+        &self,
+        runtime: &RT,
+        msg: RT::Decodable,
+        context: &Self::Context,
+        working_set: &mut WorkingSet<<Self::Context as Spec>::Storage>,
+    ) -> Result<CallResponse, Error>
+    where
+        RT: DispatchCall<Context = Self::Context>,
+    {
+        let context =
+            Self::Context::with_metadata(self.address().clone(), context.metadata().clone());
+        working_set.with_checkpoint(|working_set| {
+            working_set.charge_call().map_err(anyhow::Error::from)?;
+            let result = runtime.dispatch_call(msg, working_set, &context);
+            // The state accesses stop once the gas runs out, so the result of the call can't be trusted.
+            working_set
+                .gas_meter()
+                .check()
+                .map_err(anyhow::Error::from)?;
+            result
+        })
+    }
+}
+
+/// Describes a module of a runtime, see `#[derive(DefaultRuntime)]`.
+#[derive(Debug, PartialEq)]
+pub struct ModuleDescriptor<C: Context> {
+    /// The name of the runtime field holding the module.
+    pub name: &'static str,
+    pub address: C::Address,
+    pub prefix: Prefix,
 }
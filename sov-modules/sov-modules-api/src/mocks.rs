@@ -1,4 +1,6 @@
-use crate::{Address, AddressTrait, Context, PublicKey, SigVerificationError, Signature, Spec};
+use crate::{
+    Address, AddressTrait, Context, PublicKey, SigVerificationError, Signature, Spec, TxMetadata,
+};
 use borsh::{BorshDeserialize, BorshSerialize};
 use jmt::SimpleHasher;
 use sov_state::ZkStorage;
@@ -7,7 +9,16 @@ use sovereign_sdk::core::types::ArrayWitness;
 use std::convert::Infallible;
 
 /// Mock for Spec::PublicKey, useful for testing.
-#[derive(PartialEq, Eq, Clone, BorshDeserialize, BorshSerialize, Debug)]
+#[derive(
+    PartialEq,
+    Eq,
+    Clone,
+    BorshDeserialize,
+    BorshSerialize,
+    serde::Serialize,
+    serde::Deserialize,
+    Debug,
+)]
 pub struct MockPublicKey {
     pub_key: Vec<u8>,
 }
@@ -60,6 +71,7 @@ impl Signature for MockSignature {
 #[derive(Clone, Debug, PartialEq)]
 pub struct MockContext {
     pub sender: Address,
+    pub metadata: TxMetadata,
 }
 
 impl Spec for MockContext {
@@ -76,14 +88,19 @@ impl Context for MockContext {
         &self.sender
     }
 
-    fn new(sender: Self::Address) -> Self {
-        Self { sender }
+    fn metadata(&self) -> &TxMetadata {
+        &self.metadata
+    }
+
+    fn with_metadata(sender: Self::Address, metadata: TxMetadata) -> Self {
+        Self { sender, metadata }
     }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct ZkMockContext {
     pub sender: Address,
+    pub metadata: TxMetadata,
 }
 
 impl Spec for ZkMockContext {
@@ -100,7 +117,11 @@ impl Context for ZkMockContext {
         &self.sender
     }
 
-    fn new(sender: Self::Address) -> Self {
-        Self { sender }
+    fn metadata(&self) -> &TxMetadata {
+        &self.metadata
+    }
+
+    fn with_metadata(sender: Self::Address, metadata: TxMetadata) -> Self {
+        Self { sender, metadata }
     }
 }
@@ -4,7 +4,7 @@ use crate::{Context, Hasher};
 const DOMAIN_SEPARATOR: [u8; 1] = [47];
 
 /// A unique identifier for each state variable in a module.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Prefix {
     module_path: &'static str,
     module_name: &'static str,
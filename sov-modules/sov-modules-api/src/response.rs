@@ -1,4 +1,19 @@
-use sovereign_sdk::stf::Event;
+use sovereign_sdk::stf::{Event, EventModule};
+
+use crate::Error;
+
+/// The key of the event reporting a failed slot hook, its value is the error message.
+pub const SLOT_HOOK_FAILED_EVENT_KEY: &str = "slot_hook_failed";
+
+/// An event emitted by a module, usually an enum with one variant per kind of event.
+///
+/// The event is stored borsh encoded, so indexers can decode it with the event type of the module
+/// instead of parsing strings. It can be derived with `#[derive(ModuleEvent)]`.
+pub trait ModuleEvent: borsh::BorshSerialize {
+    /// The key under which the event is indexed. It must be unique across modules,
+    /// the derived keys are prefixed with the name of the crate of the module.
+    fn event_key(&self) -> &'static str;
+}
 
 /// Response type for the `Module::call` method.
 #[derive(Default)]
@@ -8,9 +23,39 @@ pub struct CallResponse {
 }
 
 impl CallResponse {
+    /// The response of a failed slot hook, which reports the error in an event.
+    pub fn slot_hook_failed(error: &Error) -> Self {
+        let mut response = Self::default();
+        response.add_event(SLOT_HOOK_FAILED_EVENT_KEY, &error.to_string());
+        response
+    }
+
     pub fn add_event(&mut self, key: &str, value: &str) {
         self.events.push(Event::new(key, value))
     }
+
+    /// Adds a typed event, its value is the borsh encoding of `event`.
+    pub fn add_typed_event<E: ModuleEvent>(&mut self, event: &E) {
+        let value = event
+            .try_to_vec()
+            .expect("Serialization to a vector must succeed");
+        self.events.push(Event::from_bytes(
+            event.event_key().as_bytes().to_vec(),
+            value,
+        ))
+    }
+
+    /// Attributes the events which aren't attributed yet to the given module.
+    /// Called by the runtime when it dispatches a call to the module.
+    pub fn set_module(&mut self, name: &str, address: &[u8]) {
+        let module = EventModule {
+            name: name.to_owned(),
+            address: address.to_vec(),
+        };
+        for event in self.events.iter_mut().filter(|e| e.module.is_none()) {
+            event.module = Some(module.clone());
+        }
+    }
 }
 
 /// Response type for the `Module::query` method. The response is returned by the relevant RPC call.
@@ -2,9 +2,27 @@ use crate::Accounts;
 use anyhow::Result;
 use sov_state::WorkingSet;
 
+/// Initial configuration for the Accounts module.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountConfig<C: sov_modules_api::Context> {
+    /// Public keys of the accounts created at genesis, each with its default address.
+    pub pub_keys: Vec<C::PublicKey>,
+}
+
 impl<C: sov_modules_api::Context> Accounts<C> {
-    pub(crate) fn init_module(&self, _working_set: &mut WorkingSet<C::Storage>) -> Result<()> {
-        // TODO read initial accounts from "Config"
+    pub(crate) fn init_module(
+        &self,
+        config: &AccountConfig<C>,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Result<()> {
+        for pub_key in config.pub_keys.iter() {
+            anyhow::ensure!(
+                self.accounts.get(pub_key, working_set).is_none(),
+                "Account already exists"
+            );
+            self.create_default_account(pub_key.clone(), working_set)?;
+        }
         Ok(())
     }
 }
@@ -2,7 +2,6 @@ use crate::{Account, Accounts};
 use anyhow::Result;
 use sov_modules_api::Context;
 use sov_modules_api::ModuleInfo;
-use sov_modules_api::PublicKey;
 use sov_state::WorkingSet;
 
 pub struct Hooks<C: sov_modules_api::Context> {
@@ -23,24 +22,7 @@ impl<C: Context> Hooks<C> {
     ) -> Result<Account<C>> {
         match self.inner.accounts.get(&pub_key, working_set) {
             Some(acc) => Ok(acc),
-            None => {
-                let default_address = pub_key.to_address();
-                self.exit_if_address_exists(&default_address, working_set)?;
-
-                let new_account = Account {
-                    addr: default_address.clone(),
-                    nonce: 0,
-                };
-
-                self.inner
-                    .accounts
-                    .set(&pub_key, new_account.clone(), working_set);
-
-                self.inner
-                    .public_keys
-                    .set(&default_address, pub_key, working_set);
-                Ok(new_account)
-            }
+            None => self.inner.create_default_account(pub_key, working_set),
         }
     }
 
@@ -55,16 +37,4 @@ impl<C: Context> Hooks<C> {
 
         Ok(())
     }
-
-    fn exit_if_address_exists(
-        &self,
-        address: &C::Address,
-        working_set: &mut WorkingSet<C::Storage>,
-    ) -> Result<()> {
-        anyhow::ensure!(
-            self.inner.public_keys.get(address, working_set).is_none(),
-            "Address already exists"
-        );
-        Ok(())
-    }
 }
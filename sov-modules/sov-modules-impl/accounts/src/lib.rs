@@ -6,8 +6,10 @@ mod query;
 #[cfg(test)]
 mod tests;
 
+pub use genesis::AccountConfig;
+
 use borsh::{BorshDeserialize, BorshSerialize};
-use sov_modules_api::Error;
+use sov_modules_api::{Error, PublicKey};
 use sov_modules_macros::ModuleInfo;
 use sov_state::WorkingSet;
 
@@ -36,8 +38,14 @@ impl<C: sov_modules_api::Context> sov_modules_api::Module for Accounts<C> {
 
     type QueryMessage = query::QueryMessage<C>;
 
-    fn genesis(&self, working_set: &mut WorkingSet<C::Storage>) -> Result<(), Error> {
-        Ok(self.init_module(working_set)?)
+    type Config = AccountConfig<C>;
+
+    fn genesis(
+        &self,
+        config: &Self::Config,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Result<(), Error> {
+        Ok(self.init_module(config, working_set)?)
     }
 
     fn call(
@@ -67,3 +75,38 @@ impl<C: sov_modules_api::Context> sov_modules_api::Module for Accounts<C> {
         }
     }
 }
+
+impl<C: sov_modules_api::Context> Accounts<C> {
+    /// Creates an account with the default address of `pub_key`.
+    pub(crate) fn create_default_account(
+        &self,
+        pub_key: C::PublicKey,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> anyhow::Result<Account<C>> {
+        let default_address = pub_key.to_address();
+        self.exit_if_address_exists(&default_address, working_set)?;
+
+        let new_account = Account {
+            addr: default_address.clone(),
+            nonce: 0,
+        };
+
+        self.accounts
+            .set(&pub_key, new_account.clone(), working_set);
+
+        self.public_keys.set(&default_address, pub_key, working_set);
+        Ok(new_account)
+    }
+
+    fn exit_if_address_exists(
+        &self,
+        address: &C::Address,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.public_keys.get(address, working_set).is_none(),
+            "Address already exists"
+        );
+        Ok(())
+    }
+}
@@ -1,8 +1,8 @@
-use anyhow::Result;
-use sov_modules_api::CallResponse;
+use anyhow::{anyhow, ensure, Result};
+use sov_modules_api::{CallResponse, Hasher, Spec};
 use sov_state::WorkingSet;
 
-use crate::{Amount, Bank, Coins};
+use crate::{Amount, Bank, Coins, Token};
 
 #[derive(borsh::BorshDeserialize, borsh::BorshSerialize, Debug, PartialEq)]
 pub enum CallMessage<C: sov_modules_api::Context> {
@@ -25,32 +25,191 @@ pub enum CallMessage<C: sov_modules_api::Context> {
 impl<C: sov_modules_api::Context> Bank<C> {
     pub fn create_token(
         &self,
-        _token_name: String,
-        _initial_balance: Amount,
-        _minter_address: C::Address,
-        _context: &C,
-        _working_set: &mut WorkingSet<C::Storage>,
+        token_name: String,
+        initial_balance: Amount,
+        minter_address: C::Address,
+        context: &C,
+        working_set: &mut WorkingSet<C::Storage>,
     ) -> Result<CallResponse> {
-        // This function will create a unique address for `token_name` and insert, the new `Token` to self.tokens
-        todo!()
+        let token_address = self.create(
+            &token_name,
+            context.sender(),
+            &[(minter_address, initial_balance)],
+            working_set,
+        )?;
+
+        let mut response = CallResponse::default();
+        response.add_event(
+            "token_created",
+            &format!(
+                "name: {token_name}, address: {}",
+                hex::encode(&token_address)
+            ),
+        );
+        Ok(response)
     }
 
     pub fn transfer(
         &self,
-        _to: C::Address,
-        _coins: Coins<C::Address>,
-        _context: &C,
-        _working_set: &mut WorkingSet<C::Storage>,
+        to: C::Address,
+        coins: Coins<C::Address>,
+        context: &C,
+        working_set: &mut WorkingSet<C::Storage>,
     ) -> Result<CallResponse> {
-        todo!()
+        self.tokens.get_or_err(&coins.token_address, working_set)?;
+        let from = context.sender();
+
+        let from_balance = self.balance(&coins.token_address, from, working_set);
+        let new_from_balance = from_balance.checked_sub(coins.amount).ok_or_else(|| {
+            anyhow!(
+                "Insufficient balance: {from_balance}, required: {}",
+                coins.amount
+            )
+        })?;
+        self.set_balance(&coins.token_address, from, new_from_balance, working_set);
+
+        // The recipient balance is read after the sender balance was updated, so that
+        // a transfer to oneself leaves the balance unchanged.
+        let to_balance = self.balance(&coins.token_address, &to, working_set);
+        let new_to_balance = to_balance
+            .checked_add(coins.amount)
+            .ok_or_else(|| anyhow!("Balance overflow for the recipient"))?;
+        self.set_balance(&coins.token_address, &to, new_to_balance, working_set);
+
+        let mut response = CallResponse::default();
+        response.add_event(
+            "transfer",
+            &format!(
+                "token: {}, amount: {}, to: {}",
+                hex::encode(&coins.token_address),
+                coins.amount,
+                hex::encode(&to)
+            ),
+        );
+        Ok(response)
     }
 
     pub fn burn(
         &self,
-        _coins: Coins<C::Address>,
-        _context: &C,
-        _working_set: &mut WorkingSet<C::Storage>,
+        coins: Coins<C::Address>,
+        context: &C,
+        working_set: &mut WorkingSet<C::Storage>,
     ) -> Result<CallResponse> {
-        todo!()
+        let mut token = self.tokens.get_or_err(&coins.token_address, working_set)?;
+        let from = context.sender();
+
+        let balance = self.balance(&coins.token_address, from, working_set);
+        let new_balance = balance.checked_sub(coins.amount).ok_or_else(|| {
+            anyhow!(
+                "Insufficient balance: {balance}, required: {}",
+                coins.amount
+            )
+        })?;
+        // The supply is the sum of all the balances, so it only falls short of a balance if the state is corrupted.
+        token.total_supply = token
+            .total_supply
+            .checked_sub(coins.amount)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Total supply {} is lower than the burnt amount {}",
+                    token.total_supply,
+                    coins.amount
+                )
+            })?;
+
+        self.set_balance(&coins.token_address, from, new_balance, working_set);
+        self.tokens.set(&coins.token_address, token, working_set);
+
+        let mut response = CallResponse::default();
+        response.add_event(
+            "burn",
+            &format!(
+                "token: {}, amount: {}",
+                hex::encode(&coins.token_address),
+                coins.amount
+            ),
+        );
+        Ok(response)
+    }
+
+    /// Creates a token with the given initial balances and returns its address.
+    pub(crate) fn create(
+        &self,
+        token_name: &str,
+        creator: &C::Address,
+        balances: &[(C::Address, Amount)],
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Result<C::Address> {
+        let token_name = token_name.to_owned();
+        ensure!(
+            self.names.get(&token_name, working_set).is_none(),
+            "Token {token_name} already exists"
+        );
+
+        let token_address = token_address::<C>(&token_name, creator)?;
+        ensure!(
+            self.tokens.get(&token_address, working_set).is_none(),
+            "Token address {} is already taken",
+            hex::encode(&token_address)
+        );
+
+        let mut total_supply: Amount = 0;
+        for (address, amount) in balances {
+            let balance = self.balance(&token_address, address, working_set);
+            let new_balance = balance
+                .checked_add(*amount)
+                .ok_or_else(|| anyhow!("Balance overflow for {}", hex::encode(address)))?;
+            total_supply = total_supply
+                .checked_add(*amount)
+                .ok_or_else(|| anyhow!("Total supply overflow for token {token_name}"))?;
+            self.set_balance(&token_address, address, new_balance, working_set);
+        }
+
+        let token = Token {
+            name: token_name.clone(),
+            total_supply,
+        };
+        self.names
+            .set(&token_name, token_address.clone(), working_set);
+        self.tokens.set(&token_address, token, working_set);
+
+        Ok(token_address)
+    }
+
+    /// Returns the balance of `user_address`, zero if the user never held the token.
+    pub(crate) fn balance(
+        &self,
+        token_address: &C::Address,
+        user_address: &C::Address,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Amount {
+        self.balances
+            .get(&(token_address.clone(), user_address.clone()), working_set)
+            .unwrap_or_default()
     }
+
+    fn set_balance(
+        &self,
+        token_address: &C::Address,
+        user_address: &C::Address,
+        amount: Amount,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) {
+        self.balances.set(
+            &(token_address.clone(), user_address.clone()),
+            amount,
+            working_set,
+        );
+    }
+}
+
+/// The address of a token is derived from its name and the address of its creator.
+pub fn token_address<C: sov_modules_api::Context>(
+    token_name: &str,
+    sender: &C::Address,
+) -> Result<C::Address> {
+    let mut hasher = <C as Spec>::Hasher::new();
+    hasher.update(sender.as_ref());
+    hasher.update(token_name.as_bytes());
+    C::Address::try_from(&hasher.finalize())
 }
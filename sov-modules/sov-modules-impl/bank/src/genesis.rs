@@ -1,10 +1,40 @@
-use crate::Bank;
+use crate::{Amount, Bank};
 use anyhow::Result;
 use sov_state::WorkingSet;
 
+/// Initial configuration for the Bank module.
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound = "")
+)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BankConfig<C: sov_modules_api::Context> {
+    pub tokens: Vec<TokenConfig<C>>,
+}
+
+/// A token created at genesis. Its address is derived from its name and the address of the Bank module.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenConfig<C: sov_modules_api::Context> {
+    pub token_name: String,
+    pub address_and_balances: Vec<(C::Address, Amount)>,
+}
+
 impl<C: sov_modules_api::Context> Bank<C> {
-    pub(crate) fn init_module(&self, _working_set: &mut WorkingSet<C::Storage>) -> Result<()> {
-        // TODO read initial tokens from "Config"
+    pub(crate) fn init_module(
+        &self,
+        config: &BankConfig<C>,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Result<()> {
+        for token in config.tokens.iter() {
+            self.create(
+                &token.token_name,
+                &self.address,
+                &token.address_and_balances,
+                working_set,
+            )?;
+        }
         Ok(())
     }
 }
@@ -1,23 +1,31 @@
 mod call;
 mod genesis;
+#[cfg(feature = "native")]
 mod query;
+#[cfg(test)]
+mod tests;
+
+pub use call::{token_address, CallMessage};
+pub use genesis::{BankConfig, TokenConfig};
+#[cfg(feature = "native")]
+pub use query::{BalanceResponse, QueryMessage, TotalSupplyResponse};
+
 use sov_modules_api::Error;
 use sov_modules_macros::ModuleInfo;
 use sov_state::WorkingSet;
 
-type Amount = u64;
+pub type Amount = u64;
 
 #[derive(borsh::BorshDeserialize, borsh::BorshSerialize, Debug, PartialEq)]
 pub struct Coins<Address: sov_modules_api::AddressTrait> {
-    amount: Amount,
-    token_address: Address,
+    pub amount: Amount,
+    pub token_address: Address,
 }
 
 #[derive(borsh::BorshDeserialize, borsh::BorshSerialize, Debug, PartialEq, Clone)]
-pub struct Token<Address: sov_modules_api::AddressTrait> {
+pub struct Token {
     name: String,
     total_supply: u64,
-    balances: sov_state::StateMap<Address, Amount>,
 }
 
 #[derive(ModuleInfo)]
@@ -29,7 +37,11 @@ pub struct Bank<C: sov_modules_api::Context> {
     pub names: sov_state::StateMap<String, C::Address>,
 
     #[state]
-    pub tokens: sov_state::StateMap<C::Address, Token<C::Address>>,
+    pub tokens: sov_state::StateMap<C::Address, Token>,
+
+    /// Balances keyed by the token address and the holder address.
+    #[state]
+    pub balances: sov_state::StateMap<(C::Address, C::Address), Amount>,
 }
 
 impl<C: sov_modules_api::Context> sov_modules_api::Module for Bank<C> {
@@ -37,27 +49,68 @@ impl<C: sov_modules_api::Context> sov_modules_api::Module for Bank<C> {
 
     type CallMessage = call::CallMessage<C>;
 
+    #[cfg(feature = "native")]
     type QueryMessage = query::QueryMessage<C>;
 
-    fn genesis(&self, working_set: &mut WorkingSet<C::Storage>) -> Result<(), Error> {
-        Ok(self.init_module(working_set)?)
+    type Config = BankConfig<C>;
+
+    fn genesis(
+        &self,
+        config: &Self::Config,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Result<(), Error> {
+        Ok(self.init_module(config, working_set)?)
     }
 
     fn call(
         &self,
-        _msg: Self::CallMessage,
-        _context: &Self::Context,
-        _working_set: &mut WorkingSet<C::Storage>,
+        msg: Self::CallMessage,
+        context: &Self::Context,
+        working_set: &mut WorkingSet<C::Storage>,
     ) -> Result<sov_modules_api::CallResponse, Error> {
-        todo!()
+        match msg {
+            call::CallMessage::CreateToken {
+                token_name,
+                initial_balance,
+                minter_address,
+            } => Ok(self.create_token(
+                token_name,
+                initial_balance,
+                minter_address,
+                context,
+                working_set,
+            )?),
+
+            call::CallMessage::Transfer { to, coins } => {
+                Ok(self.transfer(to, coins, context, working_set)?)
+            }
+
+            call::CallMessage::Burn { coins } => Ok(self.burn(coins, context, working_set)?),
+        }
     }
 
     #[cfg(feature = "native")]
     fn query(
         &self,
-        _msg: Self::QueryMessage,
-        _working_set: &mut WorkingSet<C::Storage>,
+        msg: Self::QueryMessage,
+        working_set: &mut WorkingSet<C::Storage>,
     ) -> sov_modules_api::QueryResponse {
-        todo!()
+        match msg {
+            query::QueryMessage::GetBalance {
+                user_address,
+                token_address,
+            } => {
+                let response =
+                    serde_json::to_vec(&self.balance_of(user_address, token_address, working_set))
+                        .unwrap();
+                sov_modules_api::QueryResponse { response }
+            }
+
+            query::QueryMessage::GetTotalSupply { token_address } => {
+                let response =
+                    serde_json::to_vec(&self.supply_of(token_address, working_set)).unwrap();
+                sov_modules_api::QueryResponse { response }
+            }
+        }
     }
 }
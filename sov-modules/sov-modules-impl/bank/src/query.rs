@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use sov_state::WorkingSet;
 
 use crate::{Amount, Bank};
@@ -14,32 +15,43 @@ pub enum QueryMessage<C: sov_modules_api::Context> {
     },
 }
 
-#[derive(borsh::BorshDeserialize, borsh::BorshSerialize, Debug, PartialEq)]
+/// The balance of a user, or None if the token doesn't exist.
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
 pub struct BalanceResponse {
-    amount: Amount,
+    pub amount: Option<Amount>,
 }
 
-#[derive(borsh::BorshDeserialize, borsh::BorshSerialize, Debug, PartialEq)]
+/// The total supply of a token, or None if the token doesn't exist.
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
 pub struct TotalSupplyResponse {
-    amount: Amount,
+    pub amount: Option<Amount>,
 }
 
 impl<C: sov_modules_api::Context> Bank<C> {
     pub fn balance_of(
         &self,
-        _user_address: C::Address,
-        _token_address: C::Address,
-        _working_set: &mut WorkingSet<C::Storage>,
+        user_address: C::Address,
+        token_address: C::Address,
+        working_set: &mut WorkingSet<C::Storage>,
     ) -> BalanceResponse {
-        todo!()
+        BalanceResponse {
+            amount: self
+                .tokens
+                .get(&token_address, working_set)
+                .map(|_| self.balance(&token_address, &user_address, working_set)),
+        }
     }
 
     pub fn supply_of(
         &self,
-        _user_address: C::Address,
-        _token_address: C::Address,
-        _working_set: &mut WorkingSet<C::Storage>,
+        token_address: C::Address,
+        working_set: &mut WorkingSet<C::Storage>,
     ) -> TotalSupplyResponse {
-        todo!()
+        TotalSupplyResponse {
+            amount: self
+                .tokens
+                .get(&token_address, working_set)
+                .map(|token| token.total_supply),
+        }
     }
 }
@@ -0,0 +1,329 @@
+use crate::{call, query, Bank, BankConfig, CallMessage, Coins, Token};
+use sov_modules_api::{
+    mocks::{MockContext, MockPublicKey},
+    Context, Module, ModuleInfo, PublicKey, Spec,
+};
+use sov_state::{ProverStorage, WorkingSet};
+
+type C = MockContext;
+
+fn address(pub_key: &'static str) -> <C as Spec>::Address {
+    MockPublicKey::try_from(pub_key)
+        .unwrap()
+        .to_address::<<C as Spec>::Address>()
+}
+
+fn query_balance(
+    bank: &Bank<C>,
+    user_address: &<C as Spec>::Address,
+    token_address: &<C as Spec>::Address,
+    working_set: &mut WorkingSet<<C as Spec>::Storage>,
+) -> Option<u64> {
+    let response: query::BalanceResponse = serde_json::from_slice(
+        &bank
+            .query(
+                query::QueryMessage::GetBalance {
+                    user_address: user_address.clone(),
+                    token_address: token_address.clone(),
+                },
+                working_set,
+            )
+            .response,
+    )
+    .unwrap();
+    response.amount
+}
+
+fn query_supply(
+    bank: &Bank<C>,
+    token_address: &<C as Spec>::Address,
+    working_set: &mut WorkingSet<<C as Spec>::Storage>,
+) -> Option<u64> {
+    let response: query::TotalSupplyResponse = serde_json::from_slice(
+        &bank
+            .query(
+                query::QueryMessage::GetTotalSupply {
+                    token_address: token_address.clone(),
+                },
+                working_set,
+            )
+            .response,
+    )
+    .unwrap();
+    response.amount
+}
+
+#[test]
+fn test_token_lifecycle() {
+    let working_set = &mut WorkingSet::new(ProverStorage::temporary());
+    let bank = Bank::<C>::new();
+
+    let creator = C::new(address("creator"));
+    let minter = address("minter");
+    let receiver = address("receiver");
+    let token_address = call::token_address::<C>("sov", creator.sender()).unwrap();
+
+    assert_eq!(query_supply(&bank, &token_address, working_set), None);
+
+    bank.call(
+        CallMessage::CreateToken {
+            token_name: "sov".to_owned(),
+            initial_balance: 100,
+            minter_address: minter.clone(),
+        },
+        &creator,
+        working_set,
+    )
+    .unwrap();
+
+    assert_eq!(query_supply(&bank, &token_address, working_set), Some(100));
+    assert_eq!(
+        query_balance(&bank, &minter, &token_address, working_set),
+        Some(100)
+    );
+    assert_eq!(
+        query_balance(
+            &bank,
+            &creator.sender().clone(),
+            &token_address,
+            working_set
+        ),
+        Some(0)
+    );
+
+    // Transfer
+    {
+        let minter_context = C::new(minter.clone());
+        bank.call(
+            CallMessage::Transfer {
+                to: receiver.clone(),
+                coins: Coins {
+                    amount: 30,
+                    token_address: token_address.clone(),
+                },
+            },
+            &minter_context,
+            working_set,
+        )
+        .unwrap();
+
+        assert_eq!(
+            query_balance(&bank, &minter, &token_address, working_set),
+            Some(70)
+        );
+        assert_eq!(
+            query_balance(&bank, &receiver, &token_address, working_set),
+            Some(30)
+        );
+
+        // A transfer to oneself doesn't change the balance.
+        bank.call(
+            CallMessage::Transfer {
+                to: minter.clone(),
+                coins: Coins {
+                    amount: 70,
+                    token_address: token_address.clone(),
+                },
+            },
+            &minter_context,
+            working_set,
+        )
+        .unwrap();
+        assert_eq!(
+            query_balance(&bank, &minter, &token_address, working_set),
+            Some(70)
+        );
+    }
+
+    // Burn
+    {
+        let receiver_context = C::new(receiver.clone());
+        bank.call(
+            CallMessage::Burn {
+                coins: Coins {
+                    amount: 10,
+                    token_address: token_address.clone(),
+                },
+            },
+            &receiver_context,
+            working_set,
+        )
+        .unwrap();
+
+        assert_eq!(
+            query_balance(&bank, &receiver, &token_address, working_set),
+            Some(20)
+        );
+        assert_eq!(query_supply(&bank, &token_address, working_set), Some(90));
+    }
+}
+
+#[test]
+fn test_invalid_calls() {
+    let working_set = &mut WorkingSet::new(ProverStorage::temporary());
+    let bank = Bank::<C>::new();
+
+    let minter = C::new(address("minter"));
+    let receiver = address("receiver");
+
+    let create_token = |initial_balance| CallMessage::CreateToken {
+        token_name: "sov".to_owned(),
+        initial_balance,
+        minter_address: minter.sender().clone(),
+    };
+    bank.call(create_token(u64::MAX), &minter, working_set)
+        .unwrap();
+
+    // The name is already taken.
+    assert!(bank.call(create_token(1), &minter, working_set).is_err());
+
+    let token_address = call::token_address::<C>("sov", minter.sender()).unwrap();
+    let coins = |amount| Coins {
+        amount,
+        token_address: token_address.clone(),
+    };
+
+    // Insufficient balance.
+    let receiver_context = C::new(receiver.clone());
+    let result = bank.call(
+        CallMessage::Transfer {
+            to: minter.sender().clone(),
+            coins: coins(1),
+        },
+        &receiver_context,
+        working_set,
+    );
+    assert_eq!(
+        result.err().unwrap().to_string(),
+        "Insufficient balance: 0, required: 1"
+    );
+    assert!(bank
+        .call(
+            CallMessage::Burn { coins: coins(1) },
+            &receiver_context,
+            working_set
+        )
+        .is_err());
+
+    // Unknown token.
+    assert!(bank
+        .call(
+            CallMessage::Transfer {
+                to: receiver.clone(),
+                coins: Coins {
+                    amount: 1,
+                    token_address: address("unknown"),
+                },
+            },
+            &minter,
+            working_set,
+        )
+        .is_err());
+    assert_eq!(
+        query_balance(&bank, &receiver, &address("unknown"), working_set),
+        None
+    );
+
+    assert_eq!(
+        query_balance(&bank, minter.sender(), &token_address, working_set),
+        Some(u64::MAX)
+    );
+
+    // A supply lower than a balance is an error, not a panic.
+    let token = bank.tokens.get(&token_address, working_set).unwrap();
+    bank.tokens.set(
+        &token_address,
+        Token {
+            total_supply: 0,
+            ..token
+        },
+        working_set,
+    );
+    let result = bank.call(CallMessage::Burn { coins: coins(1) }, &minter, working_set);
+    assert_eq!(
+        result.err().unwrap().to_string(),
+        "Total supply 0 is lower than the burnt amount 1"
+    );
+    assert_eq!(
+        query_balance(&bank, minter.sender(), &token_address, working_set),
+        Some(u64::MAX)
+    );
+}
+
+#[test]
+fn test_balances_of_different_tokens() {
+    let working_set = &mut WorkingSet::new(ProverStorage::temporary());
+    let bank = Bank::<C>::new();
+
+    let minter = C::new(address("minter"));
+    let receiver = address("receiver");
+
+    let mut token_addresses = Vec::new();
+    for (token_name, initial_balance) in [("sov", 100), ("other", 50)] {
+        bank.call(
+            CallMessage::CreateToken {
+                token_name: token_name.to_owned(),
+                initial_balance,
+                minter_address: minter.sender().clone(),
+            },
+            &minter,
+            working_set,
+        )
+        .unwrap();
+        token_addresses.push(call::token_address::<C>(token_name, minter.sender()).unwrap());
+    }
+
+    bank.call(
+        CallMessage::Transfer {
+            to: receiver.clone(),
+            coins: Coins {
+                amount: 10,
+                token_address: token_addresses[0].clone(),
+            },
+        },
+        &minter,
+        working_set,
+    )
+    .unwrap();
+
+    assert_eq!(
+        query_balance(&bank, minter.sender(), &token_addresses[0], working_set),
+        Some(90)
+    );
+    assert_eq!(
+        query_balance(&bank, minter.sender(), &token_addresses[1], working_set),
+        Some(50)
+    );
+    assert_eq!(
+        query_balance(&bank, &receiver, &token_addresses[1], working_set),
+        Some(0)
+    );
+
+    let mut holders = bank
+        .balances
+        .iter_prefix(&token_addresses[0], working_set)
+        .collect::<Vec<_>>();
+    holders.sort_by_key(|(_, amount)| *amount);
+    assert_eq!(holders, vec![(receiver, 10), (minter.sender().clone(), 90)]);
+}
+
+#[test]
+fn test_genesis() {
+    let working_set = &mut WorkingSet::new(ProverStorage::temporary());
+    let bank = Bank::<C>::new();
+
+    let holder = address("holder");
+    let config: BankConfig<C> = serde_json::from_str(&format!(
+        r#"{{"tokens":[{{"token_name":"sov","address_and_balances":[["{holder}",60],["{holder}",40]]}}]}}"#,
+        holder = hex::encode(&holder)
+    ))
+    .unwrap();
+    bank.genesis(&config, working_set).unwrap();
+
+    let token_address = call::token_address::<C>("sov", bank.address()).unwrap();
+    assert_eq!(query_supply(&bank, &token_address, working_set), Some(100));
+    assert_eq!(
+        query_balance(&bank, &holder, &token_address, working_set),
+        Some(100)
+    );
+}
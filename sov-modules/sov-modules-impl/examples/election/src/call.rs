@@ -30,8 +30,9 @@ impl<C: sov_modules_api::Context> Election<C> {
         self.exit_if_not_admin(context, working_set)?;
         self.exit_if_candidates_already_set(working_set)?;
 
-        let candidates = candidate_names.into_iter().map(Candidate::new).collect();
-        self.candidates.set(candidates, working_set);
+        for name in candidate_names {
+            self.candidates.push(Candidate::new(name), working_set);
+        }
 
         Ok(CallResponse::default())
     }
@@ -56,9 +57,8 @@ impl<C: sov_modules_api::Context> Election<C> {
     /// Votes for a candidate. Must be called by the Voter.
     pub(crate) fn make_vote(
         &self,
-        // TODO the candidates are stored in `Vec` which allows iteration, but it forces us
-        // to use candidate_index instead of candidate_name here. We will change it once
-        // we have iterator for `StateMap`.
+        // TODO the candidates are stored in a `StateVec`, which forces us to use candidate_index
+        // instead of candidate_name here. We could switch to a `StateMap` now that it supports iteration.
         candidate_index: usize,
         context: &C,
         working_set: &mut WorkingSet<C::Storage>,
@@ -75,11 +75,10 @@ impl<C: sov_modules_api::Context> Election<C> {
                 self.allowed_voters
                     .set(context.sender(), Voter::voted(), working_set);
 
-                let mut candidates = self.candidates.get_or_err(working_set)?;
-
                 // Check if a candidate exist.
-                let candidate = candidates
-                    .get_mut(candidate_index)
+                let mut candidate = self
+                    .candidates
+                    .get(candidate_index, working_set)
                     .ok_or(anyhow!("Candidate doesn't exist"))?;
 
                 candidate.count = candidate
@@ -87,7 +86,8 @@ impl<C: sov_modules_api::Context> Election<C> {
                     .checked_add(1)
                     .ok_or(anyhow!("Vote count overflow"))?;
 
-                self.candidates.set(candidates, working_set);
+                self.candidates
+                    .set(candidate_index, candidate, working_set)?;
                 Ok(CallResponse::default())
             }
         }
@@ -139,7 +139,7 @@ impl<C: sov_modules_api::Context> Election<C> {
         working_set: &mut WorkingSet<C::Storage>,
     ) -> Result<()> {
         ensure!(
-            self.candidates.get(working_set).is_none(),
+            self.candidates.is_empty(working_set),
             "Candidate already set."
         );
         Ok(())
@@ -1,14 +1,22 @@
 use super::Election;
-use anyhow::{anyhow, Result};
-use sov_modules_api::PublicKey;
+use anyhow::Result;
 use sov_state::WorkingSet;
 
-impl<C: sov_modules_api::Context> Election<C> {
-    pub(crate) fn init_module(&self, working_set: &mut WorkingSet<C::Storage>) -> Result<()> {
-        let admin_pub_key = C::PublicKey::try_from("election_admin")
-            .map_err(|_| anyhow!("Admin initialization failed"))?;
+/// Initial configuration for the Election module.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElectionConfig<C: sov_modules_api::Context> {
+    /// The address allowed to set the candidates, add voters and freeze the election.
+    pub admin: C::Address,
+}
 
-        self.admin.set(admin_pub_key.to_address(), working_set);
+impl<C: sov_modules_api::Context> Election<C> {
+    pub(crate) fn init_module(
+        &self,
+        config: &ElectionConfig<C>,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Result<()> {
+        self.admin.set(config.admin.clone(), working_set);
         self.is_frozen.set(false, working_set);
         Ok(())
     }
@@ -25,15 +25,8 @@ pub struct Election<C: sov_modules_api::Context> {
     #[state]
     pub(crate) is_frozen: sov_state::StateValue<bool>,
 
-    // There are two issues here:
-    // 1. We use `std::Vec` inside `StateValue` this might be inefficient because
-    //       on every get, we are fetching the whole vector. We will add `StateVec` type in the future,
-    //       see: https://github.com/Sovereign-Labs/sovereign/issues/33
-    //
-    // 2. It would be better to use `StateMap`, but it doesn't support iteration,
-    //      see: https://github.com/Sovereign-Labs/sovereign/issues/61
     #[state]
-    pub(crate) candidates: sov_state::StateValue<Vec<Candidate>>,
+    pub(crate) candidates: sov_state::StateVec<Candidate>,
 
     #[state]
     pub(crate) allowed_voters: sov_state::StateMap<C::Address, Voter>,
@@ -46,8 +39,14 @@ impl<C: sov_modules_api::Context> sov_modules_api::Module for Election<C> {
 
     type QueryMessage = query::QueryMessage;
 
-    fn genesis(&self, working_set: &mut WorkingSet<C::Storage>) -> Result<(), Error> {
-        Ok(self.init_module(working_set)?)
+    type Config = genesis::ElectionConfig<C>;
+
+    fn genesis(
+        &self,
+        config: &Self::Config,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Result<(), Error> {
+        Ok(self.init_module(config, working_set)?)
     }
 
     fn call(
@@ -20,8 +20,14 @@ impl<C: sov_modules_api::Context> Election<C> {
         let is_frozen = self.is_frozen.get(working_set).unwrap_or_default();
 
         if is_frozen {
-            let candidates = self.candidates.get(working_set).unwrap_or(Vec::default());
-
+            let candidates = match self
+                .candidates
+                .iter(working_set)
+                .collect::<Result<Vec<_>, _>>()
+            {
+                Ok(candidates) => candidates,
+                Err(e) => return Response::Err(e.to_string()),
+            };
             // In case of tie, returns the candidate with the higher index in the vec, it is ok for the example.
             let candidate = candidates
                 .into_iter()
@@ -1,5 +1,6 @@
 use super::{
     call::CallMessage,
+    genesis::ElectionConfig,
     query::{QueryMessage, Response},
     types::Candidate,
     Election,
@@ -34,7 +35,10 @@ fn test_module<C: Context<PublicKey = MockPublicKey>>(working_set: &mut WorkingS
 
     // Init module
     {
-        election.genesis(working_set).unwrap();
+        let config = ElectionConfig {
+            admin: admin_context.sender().clone(),
+        };
+        election.genesis(&config, working_set).unwrap();
     }
 
     // Send candidates
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use borsh::{BorshDeserialize, BorshSerialize};
-use sov_modules_api::CallResponse;
+use sov_modules_api::{CallResponse, CodedError};
+use sov_modules_macros::{ModuleError, ModuleEvent};
 use sov_state::WorkingSet;
 use std::fmt::Debug;
 use thiserror::Error;
@@ -17,9 +18,16 @@ pub enum CallMessage {
     DoSetValue(SetValue),
 }
 
-#[derive(Debug, Error)]
-enum SetValueError {
+/// The events emitted by the module.
+#[derive(BorshDeserialize, BorshSerialize, Debug, PartialEq, ModuleEvent)]
+pub enum ValueSetterEvent {
+    ValueSet { new_value: u32 },
+}
+
+#[derive(Debug, Error, ModuleError)]
+pub enum SetValueError {
     #[error("Only admin can change the value")]
+    #[code = 1]
     WrongSender,
 }
 
@@ -36,12 +44,12 @@ impl<C: sov_modules_api::Context> ValueSetter<C> {
         let admin = self.admin.get_or_err(working_set)?;
 
         if &admin != context.sender() {
-            // Here we use a custom error type.
-            Err(SetValueError::WrongSender)?;
+            // Here we use a custom error type, converted to `CodedError` so that callers get its code.
+            Err(CodedError::from(SetValueError::WrongSender))?;
         }
 
         self.value.set(new_value, working_set);
-        response.add_event("set", &format!("value_set: {new_value:?}"));
+        response.add_typed_event(&ValueSetterEvent::ValueSet { new_value });
 
         Ok(response)
     }
@@ -1,16 +1,23 @@
 use super::ValueSetter;
-use anyhow::{anyhow, Result};
-use sov_modules_api::PublicKey;
+use anyhow::Result;
 use sov_state::WorkingSet;
 
+/// Initial configuration for the ValueSetter module.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueSetterConfig<C: sov_modules_api::Context> {
+    /// The only address allowed to set the value.
+    pub admin: C::Address,
+}
+
 impl<C: sov_modules_api::Context> ValueSetter<C> {
     /// Initializes module with the `admin` role.
-    pub(crate) fn init_module(&self, working_set: &mut WorkingSet<C::Storage>) -> Result<()> {
-        let admin_pub_key = C::PublicKey::try_from("value_setter_admin")
-            .map_err(|_| anyhow!("Admin initialization failed"))?;
-
-        self.admin
-            .set(admin_pub_key.to_address::<C::Address>(), working_set);
+    pub(crate) fn init_module(
+        &self,
+        config: &ValueSetterConfig<C>,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Result<()> {
+        self.admin.set(config.admin.clone(), working_set);
         Ok(())
     }
 }
@@ -1,6 +1,8 @@
 pub mod call;
 mod genesis;
 
+pub use genesis::ValueSetterConfig;
+
 #[cfg(test)]
 mod tests;
 
@@ -35,8 +37,14 @@ impl<C: sov_modules_api::Context> sov_modules_api::Module for ValueSetter<C> {
     #[cfg(feature = "native")]
     type QueryMessage = QueryMessage;
 
-    fn genesis(&self, working_set: &mut WorkingSet<C::Storage>) -> Result<(), Error> {
-        Ok(self.init_module(working_set)?)
+    type Config = ValueSetterConfig<C>;
+
+    fn genesis(
+        &self,
+        config: &Self::Config,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Result<(), Error> {
+        Ok(self.init_module(config, working_set)?)
     }
 
     fn call(
@@ -1,13 +1,13 @@
-use super::ValueSetter;
+use super::{ValueSetter, ValueSetterConfig};
 use crate::{call, query};
 
+use borsh::BorshDeserialize;
 use sov_modules_api::{
     mocks::{MockContext, MockPublicKey, ZkMockContext},
     Address, Context, PublicKey, Spec,
 };
 use sov_modules_api::{Module, ModuleInfo};
 use sov_state::{ProverStorage, WorkingSet, ZkStorage};
-use sovereign_sdk::stf::Event;
 
 #[test]
 fn test_value_setter() {
@@ -34,7 +34,10 @@ fn test_value_setter() {
 
 fn test_value_setter_helper<C: Context>(context: C, working_set: &mut WorkingSet<C::Storage>) {
     let module = ValueSetter::<C>::new();
-    module.genesis(working_set).unwrap();
+    let config = ValueSetterConfig {
+        admin: context.sender().clone(),
+    };
+    module.genesis(&config, working_set).unwrap();
 
     let new_value = 99;
     let call_msg = call::CallMessage::DoSetValue(call::SetValue { new_value });
@@ -43,7 +46,11 @@ fn test_value_setter_helper<C: Context>(context: C, working_set: &mut WorkingSet
     {
         let call_response = module.call(call_msg, &context, working_set).unwrap();
         let event = &call_response.events[0];
-        assert_eq!(event, &Event::new("set", "value_set: 99"));
+        assert_eq!(event.key.as_ref(), b"value_setter::ValueSet");
+        assert_eq!(
+            call::ValueSetterEvent::try_from_slice(event.value.as_ref()).unwrap(),
+            call::ValueSetterEvent::ValueSet { new_value: 99 }
+        );
     }
 
     let query_msg = query::QueryMessage::GetValue;
@@ -89,8 +96,18 @@ fn test_err_on_sender_is_not_admin_helper<C: Context>(
     working_set: &mut WorkingSet<C::Storage>,
 ) {
     let module = ValueSetter::<C>::new();
-    module.genesis(working_set).unwrap();
-    let resp = module.set_value(11, &context, working_set);
-
-    assert!(resp.is_err());
+    let config = ValueSetterConfig {
+        admin: MockPublicKey::try_from("value_setter_admin")
+            .unwrap()
+            .to_address(),
+    };
+    module.genesis(&config, working_set).unwrap();
+    let call_msg = call::CallMessage::DoSetValue(call::SetValue { new_value: 11 });
+    let error = match module.call(call_msg, &context, working_set) {
+        Ok(_) => panic!("Only the admin can set the value"),
+        Err(error) => error,
+    };
+
+    assert_eq!(error.code(), call::SetValueError::WrongSender.code());
+    assert_eq!(error.to_string(), "Only admin can change the value");
 }
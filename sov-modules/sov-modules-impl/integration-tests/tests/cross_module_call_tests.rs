@@ -0,0 +1,146 @@
+use sov_modules_api::mocks::{MockContext, MockPublicKey};
+use sov_modules_api::{Context, Module, ModuleInfo, PublicKey, SlotInfo, Spec, TxMetadata};
+use sov_state::{ProverStorage, WorkingSet};
+
+type C = MockContext;
+
+pub mod treasury {
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use sov_modules_api::{CallResponse, Context, Error, Module, ModuleInfo};
+    use sov_modules_macros::{DispatchCall, ModuleInfo};
+    use sov_state::{StateValue, WorkingSet};
+
+    /// The treasury can't pay out tokens before this time.
+    pub(crate) const UNLOCK_TIME: u64 = 1_000;
+
+    /// A module which owns bank tokens and pays them out once they are unlocked.
+    #[derive(ModuleInfo)]
+    pub(crate) struct Treasury<C: Context> {
+        #[address]
+        pub address: C::Address,
+
+        #[state]
+        pub payouts: StateValue<u32>,
+
+        #[module]
+        pub bank: bank::Bank<C>,
+    }
+
+    /// The modules called by the treasury, the calls are dispatched through it.
+    #[derive(DispatchCall)]
+    pub(crate) struct Callees<C: Context> {
+        pub bank: bank::Bank<C>,
+    }
+
+    #[derive(BorshDeserialize, BorshSerialize, Debug, PartialEq)]
+    pub(crate) enum CallMessage<C: Context> {
+        Pay {
+            to: C::Address,
+            coins: bank::Coins<C::Address>,
+        },
+    }
+
+    impl<C: Context> Module for Treasury<C> {
+        type Context = C;
+        type CallMessage = CallMessage<C>;
+
+        fn call(
+            &self,
+            msg: Self::CallMessage,
+            context: &Self::Context,
+            working_set: &mut WorkingSet<C::Storage>,
+        ) -> Result<CallResponse, Error> {
+            match msg {
+                CallMessage::Pay { to, coins } => {
+                    if context.timestamp() < UNLOCK_TIME {
+                        return Err(anyhow::anyhow!("The treasury is locked").into());
+                    }
+                    // The tokens are transferred from the treasury, whoever sent the message.
+                    let callees = Callees {
+                        bank: bank::Bank::new(),
+                    };
+                    let response = self.call_module(
+                        &callees,
+                        CalleesCall::bank(bank::CallMessage::Transfer { to, coins }),
+                        context,
+                        working_set,
+                    )?;
+                    let payouts = self.payouts.get(working_set).unwrap_or_default();
+                    self.payouts.set(payouts + 1, working_set);
+                    Ok(response)
+                }
+            }
+        }
+    }
+}
+
+fn address(key: &'static str) -> <C as Spec>::Address {
+    MockPublicKey::try_from(key)
+        .unwrap()
+        .to_address::<<C as Spec>::Address>()
+}
+
+#[test]
+fn module_calls_another_module_as_sender() {
+    let working_set = &mut WorkingSet::new(ProverStorage::temporary());
+    let treasury = treasury::Treasury::<C>::new();
+    let bank_config = bank::BankConfig {
+        tokens: vec![bank::TokenConfig {
+            token_name: "sov".to_owned(),
+            address_and_balances: vec![(treasury.address().clone(), 100)],
+        }],
+    };
+    treasury.bank.genesis(&bank_config, working_set).unwrap();
+    let token_address = bank::token_address::<C>("sov", treasury.bank.address()).unwrap();
+
+    let user = address("user");
+    let pay = |amount| treasury::CallMessage::Pay {
+        to: user.clone(),
+        coins: bank::Coins {
+            amount,
+            token_address: token_address.clone(),
+        },
+    };
+    let balance = |owner: &<C as Spec>::Address, working_set: &mut WorkingSet<_>| {
+        treasury
+            .bank
+            .balance_of(owner.clone(), token_address.clone(), working_set)
+            .amount
+    };
+
+    let context_at = |timestamp| {
+        let metadata = TxMetadata {
+            slot: SlotInfo {
+                da_height: 1,
+                da_hash: [1; 32],
+                timestamp,
+            },
+            tx_hash: [2; 32],
+            sequencer: vec![3; 32],
+        };
+        C::with_metadata(user.clone(), metadata)
+    };
+
+    // The tokens are locked until the unlock time of the DA block.
+    let context = context_at(treasury::UNLOCK_TIME - 1);
+    assert!(treasury.call(pay(30), &context, working_set).is_err());
+    assert_eq!(balance(treasury.address(), working_set), Some(100));
+
+    // The user holds no tokens, the transfer succeeds because the treasury is the sender.
+    let context = context_at(treasury::UNLOCK_TIME);
+    let response = treasury.call(pay(30), &context, working_set).unwrap();
+    assert_eq!(response.events.len(), 1);
+    // The event is attributed to the bank which emitted it, not to the treasury.
+    let module = response.events[0].module.as_ref().unwrap();
+    assert_eq!(module.name, "bank");
+    assert_eq!(module.address, treasury.bank.address().as_ref());
+    assert_eq!(balance(treasury.address(), working_set), Some(70));
+    assert_eq!(balance(&user, working_set), Some(30));
+    assert_eq!(treasury.payouts.get(working_set), Some(1));
+
+    // A failing call is reverted and its error is returned to the caller.
+    assert!(treasury.call(pay(1000), &context, working_set).is_err());
+    assert_eq!(balance(treasury.address(), working_set), Some(70));
+    assert_eq!(balance(&user, working_set), Some(30));
+    assert_eq!(treasury.payouts.get(working_set), Some(1));
+}
@@ -0,0 +1,93 @@
+use super::common::parse_generic_params;
+use super::common::{StructFieldExtractor, StructNamedField};
+use syn::DeriveInput;
+
+pub(crate) struct DefaultRuntimeMacro {
+    field_extractor: StructFieldExtractor,
+}
+
+impl DefaultRuntimeMacro {
+    pub(crate) fn new(name: &'static str) -> Self {
+        Self {
+            field_extractor: StructFieldExtractor::new(name),
+        }
+    }
+
+    pub(crate) fn derive_default_runtime(
+        &self,
+        input: DeriveInput,
+    ) -> Result<proc_macro::TokenStream, syn::Error> {
+        let DeriveInput {
+            data,
+            ident,
+            generics,
+            ..
+        } = input;
+
+        let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+        let fields = self.field_extractor.get_fields_from_struct(&data)?;
+        let generic_param = parse_generic_params(&generics)?;
+
+        let field_inits = Self::make_field_inits(&fields);
+        let module_names = fields.iter().map(|field| field.ident.to_string());
+        let module_descriptors = Self::make_module_descriptors(&fields);
+
+        Ok(quote::quote! {
+            impl #impl_generics #ident #type_generics #where_clause {
+                /// The names of the modules of the runtime, in declaration order.
+                pub const MODULE_NAMES: &'static [&'static str] = &[#(#module_names),*];
+
+                /// Creates the runtime, every module is created with `ModuleInfo::new`.
+                pub fn new() -> Self {
+                    Self {
+                        #(#field_inits)*
+                    }
+                }
+
+                /// Returns the name, address and prefix of every module of the runtime, in declaration order.
+                pub fn modules(&self) -> ::std::vec::Vec<sov_modules_api::ModuleDescriptor<#generic_param>> {
+                    ::std::vec![#(#module_descriptors),*]
+                }
+            }
+
+            impl #impl_generics ::core::default::Default for #ident #type_generics #where_clause {
+                fn default() -> Self {
+                    Self::new()
+                }
+            }
+        }
+        .into())
+    }
+
+    fn make_field_inits(fields: &[StructNamedField]) -> Vec<proc_macro2::TokenStream> {
+        fields
+            .iter()
+            .map(|field| {
+                let ident = &field.ident;
+                let ty = &field.ty;
+
+                quote::quote! {
+                    #ident: <#ty as sov_modules_api::ModuleInfo>::new(),
+                }
+            })
+            .collect()
+    }
+
+    fn make_module_descriptors(fields: &[StructNamedField]) -> Vec<proc_macro2::TokenStream> {
+        fields
+            .iter()
+            .map(|field| {
+                let ident = &field.ident;
+
+                quote::quote! {
+                    sov_modules_api::ModuleDescriptor {
+                        name: stringify!(#ident),
+                        address: sov_modules_api::ModuleInfo::address(&self.#ident).clone(),
+                        prefix: sov_modules_api::ModuleInfo::prefix(&self.#ident),
+                    }
+                }
+            })
+            .collect()
+    }
+}
@@ -23,12 +23,17 @@ impl<'a> StructDef<'a> {
         let enum_ident = self.enum_ident(CALL);
         let type_generics = &self.type_generics;
 
+        // The events of the response are attributed to the module which handled the call.
         let match_legs = self.fields.iter().map(|field| {
             let name = &field.ident;
+            let ty = &field.ty;
 
             quote::quote!(
                 #enum_ident::#name(message)=>{
-                    sov_modules_api::Module::call(&self.#name, message, context, working_set)
+                    let mut response = sov_modules_api::Module::call(&self.#name, message, context, working_set)?;
+                    let address = <#ty as sov_modules_api::ModuleInfo>::address(&self.#name);
+                    response.set_module(stringify!(#name), address.as_ref());
+                    Ok(response)
                 },
             )
         });
@@ -44,6 +49,16 @@ impl<'a> StructDef<'a> {
             )
         });
 
+        let match_legs_name = self.fields.iter().map(|field| {
+            let name = &field.ident;
+
+            quote::quote!(
+                #enum_ident::#name(_)=>{
+                   stringify!(#name)
+                },
+            )
+        });
+
         let ident = &self.ident;
         let impl_generics = &self.impl_generics;
         let where_clause = self.where_clause;
@@ -81,6 +96,12 @@ impl<'a> StructDef<'a> {
                     }
                 }
 
+                fn module_name(&self, decodable: &Self::Decodable) -> &'static str {
+                    match decodable {
+                        #(#match_legs_name)*
+                    }
+                }
+
             }
         }
     }
@@ -21,21 +21,29 @@ impl GenesisMacro {
             data,
             ident,
             generics,
+            vis,
             ..
         } = input;
 
         let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
 
         let fields = self.field_extractor.get_fields_from_struct(&data)?;
+        let config_ident = quote::format_ident!("{}GenesisConfig", ident);
+        let genesis_config =
+            Self::make_genesis_config(&fields, &config_ident, &vis, &impl_generics, where_clause);
         let genesis_fn_body = Self::make_genesis_fn_body(&fields);
         let generic_param = parse_generic_params(&generics)?;
 
         // Implements the Genesis trait
         Ok(quote::quote! {
+            #genesis_config
+
             impl #impl_generics sov_modules_api::Genesis for #ident #type_generics #where_clause {
                 type Context = #generic_param;
 
-                fn genesis(&self, working_set: &mut sov_state::WorkingSet<<<Self as sov_modules_api::Genesis>::Context as sov_modules_api::Spec>::Storage>) -> core::result::Result<(), sov_modules_api::Error> {
+                type Config = #config_ident #type_generics;
+
+                fn genesis(&self, config: &Self::Config, working_set: &mut sov_state::WorkingSet<<<Self as sov_modules_api::Genesis>::Context as sov_modules_api::Spec>::Storage>) -> core::result::Result<(), sov_modules_api::Error> {
                     #(#genesis_fn_body)*
                     Ok(())
                 }
@@ -44,6 +52,58 @@ impl GenesisMacro {
         .into())
     }
 
+    /// Creates the `<Runtime>GenesisConfig` struct, which holds the configuration of every module of the runtime.
+    /// The struct and its fields have the visibility of the runtime.
+    fn make_genesis_config(
+        fields: &[StructNamedField],
+        config_ident: &proc_macro2::Ident,
+        vis: &syn::Visibility,
+        impl_generics: &syn::ImplGenerics,
+        where_clause: Option<&syn::WhereClause>,
+    ) -> proc_macro2::TokenStream {
+        let config_types: Vec<_> = fields
+            .iter()
+            .map(|field| {
+                let ty = &field.ty;
+                quote::quote! { <#ty as sov_modules_api::Module>::Config }
+            })
+            .collect();
+
+        // Serde can't infer the bounds for the configs of generic modules, so they are spelled out.
+        let serialize_bound = config_types
+            .iter()
+            .map(|config_type| format!("{config_type}: sov_modules_api::serde::Serialize,"))
+            .collect::<String>();
+        let deserialize_bound = config_types
+            .iter()
+            .map(|config_type| {
+                format!("{config_type}: sov_modules_api::serde::de::DeserializeOwned,")
+            })
+            .collect::<String>();
+
+        let config_fields = fields
+            .iter()
+            .zip(&config_types)
+            .map(|(field, config_type)| {
+                let ident = &field.ident;
+                quote::quote! {
+                    #vis #ident: #config_type,
+                }
+            });
+
+        quote::quote! {
+            /// The genesis configuration of the runtime, composed of the configurations of its modules.
+            #[derive(sov_modules_api::serde::Serialize, sov_modules_api::serde::Deserialize)]
+            #[serde(
+                crate = "sov_modules_api::serde",
+                bound(serialize = #serialize_bound, deserialize = #deserialize_bound)
+            )]
+            #vis struct #config_ident #impl_generics #where_clause {
+                #(#config_fields)*
+            }
+        }
+    }
+
     fn make_genesis_fn_body(fields: &[StructNamedField]) -> Vec<proc_macro2::TokenStream> {
         fields
             .iter()
@@ -51,7 +111,7 @@ impl GenesisMacro {
                 let ident = &field.ident;
 
                 quote::quote! {
-                    self.#ident.genesis(working_set)?;
+                    self.#ident.genesis(&config.#ident, working_set)?;
                 }
             })
             .collect()
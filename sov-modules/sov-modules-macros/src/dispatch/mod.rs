@@ -1,5 +1,7 @@
 mod common;
+pub mod default_runtime;
 pub mod dispatch_call;
 pub mod dispatch_query;
 pub mod genesis;
 pub mod message_codec;
+pub mod slot_hooks;
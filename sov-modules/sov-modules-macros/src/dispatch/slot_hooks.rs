@@ -0,0 +1,89 @@
+use super::common::parse_generic_params;
+use super::common::{StructFieldExtractor, StructNamedField};
+use syn::DeriveInput;
+
+pub(crate) struct SlotHooksMacro {
+    field_extractor: StructFieldExtractor,
+}
+
+impl SlotHooksMacro {
+    pub(crate) fn new(name: &'static str) -> Self {
+        Self {
+            field_extractor: StructFieldExtractor::new(name),
+        }
+    }
+
+    pub(crate) fn derive_slot_hooks(
+        &self,
+        input: DeriveInput,
+    ) -> Result<proc_macro::TokenStream, syn::Error> {
+        let DeriveInput {
+            data,
+            ident,
+            generics,
+            ..
+        } = input;
+
+        let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+        let fields = self.field_extractor.get_fields_from_struct(&data)?;
+        let generic_param = parse_generic_params(&generics)?;
+
+        let begin_slot_hooks = Self::make_hook_calls(&fields, quote::quote!(begin_slot_hook));
+        let end_slot_hooks = Self::make_hook_calls(&fields, quote::quote!(end_slot_hook));
+
+        Ok(quote::quote! {
+            impl #impl_generics sov_modules_api::SlotHooks for #ident #type_generics #where_clause {
+                type Context = #generic_param;
+
+                fn begin_slot_hooks(
+                    &self,
+                    slot: &sov_modules_api::SlotInfo,
+                    working_set: &mut sov_state::WorkingSet<<<Self as sov_modules_api::SlotHooks>::Context as sov_modules_api::Spec>::Storage>,
+                ) -> ::std::vec::Vec<sovereign_sdk::stf::Event> {
+                    let mut events = ::std::vec::Vec::new();
+                    #(#begin_slot_hooks)*
+                    events
+                }
+
+                fn end_slot_hooks(
+                    &self,
+                    slot: &sov_modules_api::SlotInfo,
+                    working_set: &mut sov_state::WorkingSet<<<Self as sov_modules_api::SlotHooks>::Context as sov_modules_api::Spec>::Storage>,
+                ) -> ::std::vec::Vec<sovereign_sdk::stf::Event> {
+                    let mut events = ::std::vec::Vec::new();
+                    #(#end_slot_hooks)*
+                    events
+                }
+            }
+        }
+        .into())
+    }
+
+    /// Calls the hook of every module in its own checkpoint, in the order of the fields.
+    /// The failures are collected in `events`, attributed to the module like the events of a call.
+    fn make_hook_calls(
+        fields: &[StructNamedField],
+        hook: proc_macro2::TokenStream,
+    ) -> Vec<proc_macro2::TokenStream> {
+        fields
+            .iter()
+            .map(|field| {
+                let ident = &field.ident;
+                let ty = &field.ty;
+
+                quote::quote! {
+                    // A failing hook is reverted, the hooks of the other modules still run.
+                    if let Err(error) = working_set.with_checkpoint(|working_set| {
+                        <#ty as sov_modules_api::Module>::#hook(&self.#ident, slot, working_set)
+                    }) {
+                        let mut response = sov_modules_api::CallResponse::slot_hook_failed(&error);
+                        let address = <#ty as sov_modules_api::ModuleInfo>::address(&self.#ident);
+                        response.set_module(stringify!(#ident), address.as_ref());
+                        events.extend(response.events);
+                    }
+                }
+            })
+            .collect()
+    }
+}
@@ -1,8 +1,11 @@
 mod dispatch;
+mod module_error;
+mod module_event;
 mod module_info;
 use dispatch::{
-    dispatch_call::DispatchCallMacro, dispatch_query::DispatchQueryMacro, genesis::GenesisMacro,
-    message_codec::MessageCodec,
+    default_runtime::DefaultRuntimeMacro, dispatch_call::DispatchCallMacro,
+    dispatch_query::DispatchQueryMacro, genesis::GenesisMacro, message_codec::MessageCodec,
+    slot_hooks::SlotHooksMacro,
 };
 use proc_macro::TokenStream;
 use syn::parse_macro_input;
@@ -39,7 +42,58 @@ pub fn module_info(input: TokenStream) -> TokenStream {
     handle_macro_error(module_info::derive_module_info(input))
 }
 
+/// Derives the conversions from a module error enum to `sov-modules-api::CodedError` and `sov-modules-api::Error`.
+///
+/// Every variant must set its code with `#[code = N]`: the codes are unique within the module and never 0,
+/// which is reserved for errors without a code. The module of the error is the name of the crate deriving it,
+/// and the message is taken from the `Display` implementation of the enum.
+/// ## Example
+///
+/// ``` ignore
+///  #[derive(Debug, thiserror::Error, ModuleError)]
+///  pub enum SetValueError {
+///     #[error("Only admin can change the value")]
+///     #[code = 1]
+///     WrongSender,
+///  }
+///
+///  // Inside a function returning `anyhow::Result`, the code is preserved:
+///  Err(CodedError::from(SetValueError::WrongSender))?;
+/// ```
+#[proc_macro_derive(ModuleError, attributes(code))]
+pub fn module_error(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input);
+
+    handle_macro_error(module_error::derive_module_error(input))
+}
+
+/// Derives the `sov-modules-api::ModuleEvent` implementation for a module event enum.
+///
+/// The key of an event is the name of its variant prefixed with the name of the crate of the module,
+/// e.g. `value_setter::ValueSet`, so indexers can select the events of a kind without decoding them,
+/// and the events of different modules don't share a key.
+/// ## Example
+///
+/// ``` ignore
+///  #[derive(BorshSerialize, BorshDeserialize, ModuleEvent)]
+///  pub enum ValueSetterEvent {
+///     ValueSet { new_value: u32 },
+///  }
+///
+///  // Inside `Module::call`:
+///  response.add_typed_event(&ValueSetterEvent::ValueSet { new_value });
+/// ```
+#[proc_macro_derive(ModuleEvent)]
+pub fn module_event(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input);
+
+    handle_macro_error(module_event::derive_module_event(input))
+}
+
 /// Derives the `sov-modules-api::Genesis` implementation for the underlying type.
+///
+/// The macro also generates a `<Runtime>GenesisConfig` struct, with the visibility of the runtime, holding the
+/// `Config` of each module. It implements serde traits whenever all the module configs do, so it can be loaded from JSON.
 #[proc_macro_derive(Genesis)]
 pub fn genesis(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input);
@@ -48,6 +102,29 @@ pub fn genesis(input: TokenStream) -> TokenStream {
     handle_macro_error(genesis_macro.derive_genesis(input))
 }
 
+/// Derives the constructor of a runtime and the `Default` trait for it.
+///
+/// Every field of the runtime must be a module. The macro also generates the `MODULE_NAMES` constant
+/// and the `modules` method, which return the names and the `sov-modules-api::ModuleDescriptor`s of the modules.
+#[proc_macro_derive(DefaultRuntime)]
+pub fn default_runtime(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input);
+    let default_runtime_macro = DefaultRuntimeMacro::new("DefaultRuntime");
+
+    handle_macro_error(default_runtime_macro.derive_default_runtime(input))
+}
+
+/// Derives the `sov-modules-api::SlotHooks` implementation for the underlying type.
+///
+/// The hooks of the modules are called in the order of the fields of the runtime.
+#[proc_macro_derive(SlotHooks)]
+pub fn slot_hooks(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input);
+    let slot_hooks_macro = SlotHooksMacro::new("SlotHooks");
+
+    handle_macro_error(slot_hooks_macro.derive_slot_hooks(input))
+}
+
 /// Derives the `sov-modules-api::DispatchCall` implementation for the underlying type.
 #[proc_macro_derive(DispatchCall)]
 pub fn dispatch_call(input: TokenStream) -> TokenStream {
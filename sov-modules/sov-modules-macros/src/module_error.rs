@@ -0,0 +1,116 @@
+use syn::{DeriveInput, Fields};
+
+pub(crate) fn derive_module_error(
+    input: DeriveInput,
+) -> Result<proc_macro::TokenStream, syn::Error> {
+    let DeriveInput {
+        data,
+        ident,
+        generics,
+        ..
+    } = input;
+
+    let variants = match data {
+        syn::Data::Enum(en) => en.variants,
+        syn::Data::Struct(st) => {
+            return Err(syn::Error::new_spanned(
+                st.struct_token,
+                "The `ModuleError` macro supports enums only.",
+            ))
+        }
+        syn::Data::Union(un) => {
+            return Err(syn::Error::new_spanned(
+                un.union_token,
+                "The `ModuleError` macro supports enums only.",
+            ))
+        }
+    };
+
+    let mut codes = Vec::new();
+    let mut used = std::collections::BTreeMap::new();
+    for variant in variants.iter() {
+        let code = variant_code(variant)?;
+        if let Some(other) = used.insert(code, &variant.ident) {
+            return Err(syn::Error::new_spanned(
+                &variant.ident,
+                format!("The code {code} is already used by `{other}`."),
+            ));
+        }
+        codes.push(code);
+    }
+
+    let match_legs = variants.iter().zip(codes).map(|(variant, code)| {
+        let variant_ident = &variant.ident;
+        let pattern = match variant.fields {
+            Fields::Named(_) => quote::quote!(Self::#variant_ident { .. }),
+            Fields::Unnamed(_) => quote::quote!(Self::#variant_ident(..)),
+            Fields::Unit => quote::quote!(Self::#variant_ident),
+        };
+
+        quote::quote! {
+            #pattern => #code,
+        }
+    });
+
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote::quote! {
+        impl #impl_generics #ident #type_generics #where_clause {
+            /// Returns the code of the error, set on its variant with `#[code = N]`.
+            pub fn code(&self) -> u32 {
+                match self {
+                    #(#match_legs)*
+                }
+            }
+        }
+
+        impl #impl_generics ::core::convert::From<#ident #type_generics> for sov_modules_api::CodedError #where_clause {
+            fn from(error: #ident #type_generics) -> Self {
+                // The crate name is the one of the crate deriving the error, i.e. of the module.
+                Self {
+                    module: env!("CARGO_CRATE_NAME").to_owned(),
+                    code: error.code(),
+                    message: error.to_string(),
+                }
+            }
+        }
+
+        impl #impl_generics ::core::convert::From<#ident #type_generics> for sov_modules_api::Error #where_clause {
+            fn from(error: #ident #type_generics) -> Self {
+                Self::Coded(error.into())
+            }
+        }
+    }
+    .into())
+}
+
+// Codes are explicit, so that reordering the variants doesn't change them. 0 is reserved for errors without a code.
+fn variant_code(variant: &syn::Variant) -> Result<u32, syn::Error> {
+    let attribute = variant
+        .attrs
+        .iter()
+        .find(|attribute| attribute.path.is_ident("code"))
+        .ok_or_else(|| {
+            syn::Error::new_spanned(
+                &variant.ident,
+                "This variant is missing a code: add `#[code = N]`, with N > 0.",
+            )
+        })?;
+
+    match attribute.parse_meta()? {
+        syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Int(lit),
+            ..
+        }) => match lit.base10_parse::<u32>()? {
+            0 => Err(syn::Error::new_spanned(
+                lit,
+                "The code 0 is reserved for errors without a code.",
+            )),
+            code => Ok(code),
+        },
+        meta => Err(syn::Error::new_spanned(
+            meta,
+            "The code must be set as `#[code = N]`, with N > 0.",
+        )),
+    }
+}
@@ -0,0 +1,55 @@
+use syn::{DeriveInput, Fields};
+
+pub(crate) fn derive_module_event(
+    input: DeriveInput,
+) -> Result<proc_macro::TokenStream, syn::Error> {
+    let DeriveInput {
+        data,
+        ident,
+        generics,
+        ..
+    } = input;
+
+    let variants = match data {
+        syn::Data::Enum(en) => en.variants,
+        syn::Data::Struct(st) => {
+            return Err(syn::Error::new_spanned(
+                st.struct_token,
+                "The `ModuleEvent` macro supports enums only.",
+            ))
+        }
+        syn::Data::Union(un) => {
+            return Err(syn::Error::new_spanned(
+                un.union_token,
+                "The `ModuleEvent` macro supports enums only.",
+            ))
+        }
+    };
+
+    let match_legs = variants.iter().map(|variant| {
+        let variant_ident = &variant.ident;
+        let pattern = match variant.fields {
+            Fields::Named(_) => quote::quote!(Self::#variant_ident { .. }),
+            Fields::Unnamed(_) => quote::quote!(Self::#variant_ident(..)),
+            Fields::Unit => quote::quote!(Self::#variant_ident),
+        };
+
+        // The crate name is the one of the crate deriving the event, i.e. of the module.
+        quote::quote! {
+            #pattern => concat!(env!("CARGO_CRATE_NAME"), "::", stringify!(#variant_ident)),
+        }
+    });
+
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote::quote! {
+        impl #impl_generics sov_modules_api::ModuleEvent for #ident #type_generics #where_clause {
+            fn event_key(&self) -> &'static str {
+                match self {
+                    #(#match_legs)*
+                }
+            }
+        }
+    }
+    .into())
+}
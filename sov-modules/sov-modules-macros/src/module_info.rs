@@ -131,6 +131,10 @@ impl<'a> StructDef<'a> {
                 }
 
                 #fn_address
+
+                fn prefix(&self) -> sov_modules_api::Prefix {
+                    sov_modules_api::Prefix::new_module(module_path!(), stringify!(#ident))
+                }
             }
         })
     }
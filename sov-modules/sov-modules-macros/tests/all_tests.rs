@@ -16,4 +16,21 @@ fn module_dispatch_tests() {
     let t = trybuild::TestCases::new();
     t.pass("tests/dispatch/derive_genesis.rs");
     t.pass("tests/dispatch/derive_dispatch.rs");
+    t.pass("tests/dispatch/derive_default_runtime.rs");
+    t.pass("tests/dispatch/derive_slot_hooks.rs");
+}
+
+#[test]
+fn module_error_tests() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/module_error/derive_module_error.rs");
+    t.compile_fail("tests/module_error/missing_code.rs");
+    t.compile_fail("tests/module_error/duplicate_code.rs");
+    t.compile_fail("tests/module_error/reserved_code.rs");
+}
+
+#[test]
+fn module_event_tests() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/module_event/derive_module_event.rs");
 }
@@ -0,0 +1,31 @@
+mod modules;
+
+use modules::{first_test_module, second_test_module};
+use sov_modules_api::mocks::MockContext;
+use sov_modules_api::{Context, ModuleInfo};
+use sov_modules_macros::DefaultRuntime;
+
+#[derive(DefaultRuntime)]
+struct Runtime<C>
+where
+    C: Context,
+{
+    first: first_test_module::FirstTestStruct<C>,
+    second: second_test_module::SecondTestStruct<C>,
+}
+
+fn main() {
+    type RT = Runtime<MockContext>;
+    let runtime = RT::default();
+
+    assert_eq!(RT::MODULE_NAMES, &["first", "second"]);
+
+    let modules = runtime.modules();
+    assert_eq!(modules.len(), 2);
+    assert_eq!(modules[0].name, "first");
+    assert_eq!(&modules[0].address, runtime.first.address());
+    assert_eq!(modules[0].prefix, runtime.first.prefix());
+    assert_eq!(modules[1].name, "second");
+    assert_eq!(&modules[1].address, runtime.second.address());
+    assert_ne!(modules[0].prefix, modules[1].prefix);
+}
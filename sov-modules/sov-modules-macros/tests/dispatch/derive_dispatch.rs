@@ -28,7 +28,11 @@ fn main() {
 
     let storage = ProverStorage::temporary();
     let working_set = &mut sov_state::WorkingSet::new(storage);
-    runtime.genesis(working_set).unwrap();
+    let config = RuntimeGenesisConfig {
+        first: 1,
+        second: (),
+    };
+    runtime.genesis(&config, working_set).unwrap();
     let context = MockContext::new(Address::try_from([0; 32].as_ref()).unwrap());
 
     let value = 11;
@@ -38,9 +42,15 @@ fn main() {
         let module = RT::decode_call(&serialized_message).unwrap();
 
         assert_eq!(runtime.module_address(&module), runtime.first.address());
-        let _ = runtime
+        assert_eq!(runtime.module_name(&module), "first");
+        let response = runtime
             .dispatch_call(module, working_set, &context)
             .unwrap();
+
+        // The events are attributed to the module which emitted them.
+        let event_module = response.events[0].module.as_ref().unwrap();
+        assert_eq!(event_module.name, "first");
+        assert_eq!(event_module.address, runtime.first.address().as_ref());
     }
 
     {
@@ -57,6 +67,7 @@ fn main() {
         let module = RT::decode_call(&serialized_message).unwrap();
 
         assert_eq!(runtime.module_address(&module), runtime.second.address());
+        assert_eq!(runtime.module_name(&module), "second");
 
         let _ = runtime
             .dispatch_call(module, working_set, &context)
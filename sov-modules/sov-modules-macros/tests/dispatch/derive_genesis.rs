@@ -32,12 +32,13 @@ fn main() {
     let storage = ProverStorage::temporary();
     let working_set = &mut sov_state::WorkingSet::new(storage);
     let runtime = &mut Runtime::<C>::new();
-    runtime.genesis(working_set).unwrap();
+    let config: RuntimeGenesisConfig<C> = serde_json::from_str(r#"{"first":7,"second":null}"#).unwrap();
+    runtime.genesis(&config, working_set).unwrap();
 
     {
         let message = RuntimeQuery::<C>::first(());
         let response = runtime.dispatch_query(message, working_set);
-        assert_eq!(response.response, vec![1]);
+        assert_eq!(response.response, vec![7]);
     }
 
     {
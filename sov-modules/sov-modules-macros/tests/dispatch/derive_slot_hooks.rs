@@ -0,0 +1,108 @@
+use sov_modules_api::mocks::MockContext;
+use sov_modules_api::{
+    Context, Error, Module, ModuleInfo, SlotHooks, SlotInfo, SLOT_HOOK_FAILED_EVENT_KEY,
+};
+use sov_modules_macros::{ModuleInfo, SlotHooks};
+use sov_state::{ProverStorage, StateValue, WorkingSet};
+
+mod slot_counter {
+    use super::*;
+
+    #[derive(ModuleInfo)]
+    pub(crate) struct SlotCounter<C: Context> {
+        #[address]
+        pub(crate) address: C::Address,
+
+        #[state]
+        pub(crate) last_begun: StateValue<u64>,
+
+        #[state]
+        pub(crate) last_ended: StateValue<u64>,
+    }
+
+    impl<C: Context> Module for SlotCounter<C> {
+        type Context = C;
+
+        fn begin_slot_hook(
+            &self,
+            slot: &SlotInfo,
+            working_set: &mut WorkingSet<C::Storage>,
+        ) -> Result<(), Error> {
+            self.last_begun.set(slot.da_height, working_set);
+            Ok(())
+        }
+
+        fn end_slot_hook(
+            &self,
+            slot: &SlotInfo,
+            working_set: &mut WorkingSet<C::Storage>,
+        ) -> Result<(), Error> {
+            self.last_ended.set(slot.da_height, working_set);
+            Ok(())
+        }
+    }
+}
+
+mod failing_hook {
+    use super::*;
+
+    #[derive(ModuleInfo)]
+    pub(crate) struct FailingHook<C: Context> {
+        #[address]
+        pub(crate) address: C::Address,
+
+        #[state]
+        pub(crate) value: StateValue<u64>,
+    }
+
+    impl<C: Context> Module for FailingHook<C> {
+        type Context = C;
+
+        fn begin_slot_hook(
+            &self,
+            slot: &SlotInfo,
+            working_set: &mut WorkingSet<C::Storage>,
+        ) -> Result<(), Error> {
+            self.value.set(slot.da_height, working_set);
+            Err(anyhow::anyhow!("hook failed").into())
+        }
+    }
+}
+
+#[derive(SlotHooks)]
+struct Runtime<C: Context> {
+    failing: failing_hook::FailingHook<C>,
+    counter: slot_counter::SlotCounter<C>,
+}
+
+fn main() {
+    let runtime = Runtime::<MockContext> {
+        failing: failing_hook::FailingHook::new(),
+        counter: slot_counter::SlotCounter::new(),
+    };
+    let working_set = &mut WorkingSet::new(ProverStorage::temporary());
+    let slot = SlotInfo {
+        da_height: 7,
+        da_hash: [7; 32],
+        timestamp: 70,
+    };
+
+    let events = runtime.begin_slot_hooks(&slot, working_set);
+    assert_eq!(runtime.counter.last_begun.get(working_set), Some(7));
+    assert_eq!(runtime.counter.last_ended.get(working_set), None);
+    // The changes of the failing hook are reverted, and its error is reported.
+    assert_eq!(runtime.failing.value.get(working_set), None);
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events[0].key.as_ref(),
+        SLOT_HOOK_FAILED_EVENT_KEY.as_bytes()
+    );
+    assert_eq!(events[0].value.as_ref(), b"hook failed");
+    let module = events[0].module.as_ref().unwrap();
+    assert_eq!(module.name, "failing");
+    assert_eq!(module.address, runtime.failing.address().as_ref());
+
+    let events = runtime.end_slot_hooks(&slot, working_set);
+    assert_eq!(runtime.counter.last_ended.get(working_set), Some(7));
+    assert!(events.is_empty());
+}
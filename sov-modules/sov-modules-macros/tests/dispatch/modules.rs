@@ -18,9 +18,14 @@ pub mod first_test_module {
         type Context = C;
         type CallMessage = u8;
         type QueryMessage = ();
+        type Config = u8;
 
-        fn genesis(&self, working_set: &mut WorkingSet<C::Storage>) -> Result<(), Error> {
-            self.state_in_first_struct.set(1, working_set);
+        fn genesis(
+            &self,
+            config: &Self::Config,
+            working_set: &mut WorkingSet<C::Storage>,
+        ) -> Result<(), Error> {
+            self.state_in_first_struct.set(*config, working_set);
             Ok(())
         }
 
@@ -31,7 +36,9 @@ pub mod first_test_module {
             working_set: &mut WorkingSet<C::Storage>,
         ) -> Result<CallResponse, Error> {
             self.state_in_first_struct.set(msg, working_set);
-            Ok(CallResponse::default())
+            let mut response = CallResponse::default();
+            response.add_event("set", &msg.to_string());
+            Ok(response)
         }
 
         fn query(
@@ -67,7 +74,11 @@ pub mod second_test_module {
         type CallMessage = u8;
         type QueryMessage = TestType;
 
-        fn genesis(&self, working_set: &mut WorkingSet<C::Storage>) -> Result<(), Error> {
+        fn genesis(
+            &self,
+            _config: &Self::Config,
+            working_set: &mut WorkingSet<C::Storage>,
+        ) -> Result<(), Error> {
             self.state_in_second_struct.set(2, working_set);
             Ok(())
         }
@@ -0,0 +1,41 @@
+use sov_modules_api::{CodedError, Error};
+use sov_modules_macros::ModuleError;
+
+#[derive(Debug, thiserror::Error, ModuleError)]
+enum TestError {
+    #[error("First error")]
+    #[code = 3]
+    First,
+    #[error("Second error: {0}")]
+    #[code = 1]
+    Second(u32),
+    #[error("Third error: {value}")]
+    #[code = 2]
+    Third { value: u32 },
+}
+
+fn fails() -> anyhow::Result<()> {
+    Err(CodedError::from(TestError::Second(7)))?
+}
+
+fn main() {
+    // The codes are the explicit ones, not the positions of the variants.
+    assert_eq!(TestError::First.code(), 3);
+    assert_eq!(TestError::Second(0).code(), 1);
+    assert_eq!(TestError::Third { value: 0 }.code(), 2);
+
+    let coded = CodedError::from(TestError::Third { value: 5 });
+    assert_eq!(coded.module, env!("CARGO_CRATE_NAME"));
+    assert_eq!(coded.code, 2);
+    assert_eq!(coded.message, "Third error: 5");
+
+    // The code survives the conversion through anyhow.
+    let error = Error::from(fails().unwrap_err());
+    assert_eq!(error.module(), Some(env!("CARGO_CRATE_NAME")));
+    assert_eq!(error.code(), 1);
+    assert_eq!(error.to_string(), "Second error: 7");
+
+    let untyped = Error::from(anyhow::anyhow!("untyped"));
+    assert_eq!(untyped.module(), None);
+    assert_eq!(untyped.code(), 0);
+}
@@ -0,0 +1,13 @@
+use sov_modules_macros::ModuleError;
+
+#[derive(Debug, thiserror::Error, ModuleError)]
+enum TestError {
+    #[error("First error")]
+    #[code = 1]
+    First,
+    #[error("Second error")]
+    #[code = 1]
+    Second,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use sov_modules_macros::ModuleError;
+
+#[derive(Debug, thiserror::Error, ModuleError)]
+enum TestError {
+    #[error("First error")]
+    #[code = 1]
+    First,
+    #[error("Second error")]
+    Second,
+}
+
+fn main() {}
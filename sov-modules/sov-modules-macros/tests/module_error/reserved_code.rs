@@ -0,0 +1,10 @@
+use sov_modules_macros::ModuleError;
+
+#[derive(Debug, thiserror::Error, ModuleError)]
+enum TestError {
+    #[error("First error")]
+    #[code = 0]
+    First,
+}
+
+fn main() {}
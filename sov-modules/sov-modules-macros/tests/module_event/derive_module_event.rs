@@ -0,0 +1,33 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use sov_modules_api::{CallResponse, ModuleEvent};
+use sov_modules_macros::ModuleEvent;
+
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize, ModuleEvent)]
+enum TestEvent {
+    Created,
+    Updated(u32),
+    Deleted { id: u64 },
+}
+
+fn main() {
+    // The keys are prefixed with the name of the crate defining the events.
+    let key = |variant: &str| format!("{}::{variant}", env!("CARGO_CRATE_NAME"));
+    assert_eq!(TestEvent::Created.event_key(), key("Created"));
+    assert_eq!(TestEvent::Updated(1).event_key(), key("Updated"));
+    assert_eq!(TestEvent::Deleted { id: 1 }.event_key(), key("Deleted"));
+
+    let mut response = CallResponse::default();
+    response.add_typed_event(&TestEvent::Deleted { id: 3 });
+
+    // The payload is decoded without parsing strings.
+    let event = &response.events[0];
+    assert_eq!(event.key.as_ref(), key("Deleted").as_bytes());
+    assert_eq!(
+        TestEvent::try_from_slice(event.value.as_ref()).unwrap(),
+        TestEvent::Deleted { id: 3 }
+    );
+    assert!(event.module.is_none());
+
+    response.set_module("test", &[1; 32]);
+    assert_eq!(response.events[0].module.as_ref().unwrap().name, "test");
+}
@@ -0,0 +1,138 @@
+//! Gas metering of the state accesses.
+//!
+//! Following the two-tier model of `notes/state.md`, compute and storage are accounted separately:
+//! reads, writes and witness hints have fixed compute costs, while the bytes written to the state are
+//! charged to the storage tier, so that the price of storage growth can be adjusted on its own.
+use thiserror::Error;
+
+/// The gas charged for each kind of state access.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GasCosts {
+    /// Compute cost of dispatching a call to a module, charged before the call runs.
+    pub call: u64,
+    /// Compute cost of a read.
+    pub read: u64,
+    /// Compute cost of a write or a deletion.
+    pub write: u64,
+    /// Compute cost of a read which misses the cache, and so adds a hint to the witness.
+    pub witness_hint: u64,
+    /// Storage cost of every byte written, key and value included.
+    pub byte_stored: u64,
+}
+
+impl Default for GasCosts {
+    fn default() -> Self {
+        Self {
+            call: 500,
+            read: 10,
+            write: 50,
+            witness_hint: 100,
+            byte_stored: 2,
+        }
+    }
+}
+
+/// The gas used, by tier.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GasUsed {
+    pub compute: u64,
+    pub storage: u64,
+}
+
+impl GasUsed {
+    pub fn total(&self) -> u64 {
+        self.compute.saturating_add(self.storage)
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("Out of gas: used {used}, limit {limit}")]
+pub struct OutOfGas {
+    pub used: u64,
+    pub limit: u64,
+}
+
+/// Accumulates the gas charged for the state accesses of a working set.
+///
+/// Once the limit is exceeded the meter stays out of gas, and the working set stops accessing the state:
+/// the `try_*` accesses of the working set return [`OutOfGas`], while the others read nothing and drop the
+/// writes, so a call can't do more work than it paid for. It is up to the caller to revert the changes made
+/// with the meter, see [`GasMeter::check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GasMeter {
+    limit: u64,
+    costs: GasCosts,
+    used: GasUsed,
+}
+
+impl Default for GasMeter {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
+
+impl GasMeter {
+    pub fn new(limit: u64, costs: GasCosts) -> Self {
+        Self {
+            limit,
+            costs,
+            used: GasUsed::default(),
+        }
+    }
+
+    /// A meter which never runs out of gas, used outside of transactions.
+    pub fn unlimited() -> Self {
+        Self::new(u64::MAX, GasCosts::default())
+    }
+
+    /// Replaces the limit, keeping the gas used so far.
+    pub fn with_limit(self, limit: u64) -> Self {
+        Self { limit, ..self }
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    pub fn used(&self) -> GasUsed {
+        self.used
+    }
+
+    /// Charges the base cost of a call.
+    pub fn charge_call(&mut self) -> Result<(), OutOfGas> {
+        self.charge_compute(self.costs.call)
+    }
+
+    /// Returns an error if more gas than the limit was charged.
+    pub fn check(&self) -> Result<(), OutOfGas> {
+        let used = self.used.total();
+        if used > self.limit {
+            return Err(OutOfGas {
+                used,
+                limit: self.limit,
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn charge_reads(&mut self, count: usize) -> Result<(), OutOfGas> {
+        self.charge_compute(self.costs.read.saturating_mul(count as u64))
+    }
+
+    pub(crate) fn charge_witness_hint(&mut self) -> Result<(), OutOfGas> {
+        self.charge_compute(self.costs.witness_hint)
+    }
+
+    pub(crate) fn charge_write(&mut self, bytes_stored: usize) -> Result<(), OutOfGas> {
+        self.used.storage = self
+            .used
+            .storage
+            .saturating_add(self.costs.byte_stored.saturating_mul(bytes_stored as u64));
+        self.charge_compute(self.costs.write)
+    }
+
+    fn charge_compute(&mut self, gas: u64) -> Result<(), OutOfGas> {
+        self.used.compute = self.used.compute.saturating_add(gas);
+        self.check()
+    }
+}
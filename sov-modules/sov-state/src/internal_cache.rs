@@ -1,6 +1,9 @@
+use std::{collections::BTreeMap, sync::Arc};
+
 use crate::{
+    key_index,
     storage::{StorageKey, StorageValue},
-    Storage,
+    Prefix, Storage,
 };
 use first_read_last_write_cache::{
     cache::{self, CacheLog, ValueExists},
@@ -48,6 +51,51 @@ impl StorageInternalCache {
         }
     }
 
+    /// Gets all the entries under the given prefix, merging the values fetched from the provided
+    /// `ValueReader` with the values in the cache. The entries are found by following the key index
+    /// from the key before the prefix, and every index entry and value is read through the cache,
+    /// so they are all checked during `validate_and_commit`.
+    pub(crate) fn get_or_fetch_by_prefix<S: Storage>(
+        &mut self,
+        prefix: &Prefix,
+        value_reader: &S,
+        witness: &S::Witness,
+    ) -> Vec<(StorageKey, StorageValue)> {
+        let key_before = value_reader.key_before(prefix, witness);
+        let fetched = key_index::read_by_prefix(prefix, key_before, |key| {
+            self.get_or_fetch(key, value_reader, witness)
+        });
+
+        self.overlay_by_prefix(prefix, fetched)
+    }
+
+    /// Overlays the cached values under the given prefix on top of `entries`.
+    /// The result is ordered by key, and keys deleted in the cache are dropped from it.
+    pub(crate) fn overlay_by_prefix(
+        &self,
+        prefix: &Prefix,
+        entries: Vec<(StorageKey, StorageValue)>,
+    ) -> Vec<(StorageKey, StorageValue)> {
+        let mut merged: BTreeMap<Arc<Vec<u8>>, (StorageKey, Option<StorageValue>)> = entries
+            .into_iter()
+            .map(|(key, value)| (key.key(), (key, Some(value))))
+            .collect();
+
+        for (cache_key, cache_value) in self
+            .tx_cache
+            .get_values_with_prefix(prefix.as_aligned_vec().as_ref())
+        {
+            let key = StorageKey::new_from_cache_key(cache_key);
+            let value = cache_value.map(StorageValue::new_from_cache_value);
+            merged.insert(key.key(), (key, value));
+        }
+
+        merged
+            .into_values()
+            .filter_map(|(key, value)| value.map(|v| (key, v)))
+            .collect()
+    }
+
     pub fn try_get(&self, key: StorageKey) -> ValueExists {
         let cache_key = key.as_cache_key();
         self.get_value_from_cache(cache_key)
@@ -64,6 +112,12 @@ impl StorageInternalCache {
         self.tx_cache.add_write(cache_key, None);
     }
 
+    /// Returns true if the key was read or written before.
+    pub(crate) fn contains(&self, key: &StorageKey) -> bool {
+        let cache_key = key.clone().as_cache_key();
+        matches!(self.get_value_from_cache(cache_key), ValueExists::Yes(_))
+    }
+
     fn get_value_from_cache(&self, cache_key: CacheKey) -> cache::ValueExists {
         self.tx_cache.get_value(&cache_key)
     }
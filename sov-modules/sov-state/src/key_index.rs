@@ -0,0 +1,293 @@
+//! The JMT orders its leaves by key hash, so it can't prove that some entries are all the entries under a prefix.
+//! The state therefore commits to the order of its keys too: every stored key has an entry in the key index,
+//! holding the next stored key in byte order, and the head of the index holds the first one. The index is stored
+//! in the tree under a reserved prefix, so its entries are read and proven like any other value.
+//!
+//! The entries under a prefix are read from the last stored key ordered before it, which is a hint of the storage,
+//! by following the index for as long as the keys start with the prefix. The index is updated when committing,
+//! for the keys whose writes add or remove a value.
+
+use std::collections::BTreeMap;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::{
+    storage::{StorageKey, StorageValue},
+    Prefix,
+};
+
+/// The prefix of the index. Its first byte can't start a module prefix, as those are valid UTF-8.
+/// The head of the index is stored under the prefix itself.
+pub(crate) const KEY_INDEX_PREFIX: &[u8] = b"\xffkey_index";
+const KEY_INDEX_SEPARATOR: u8 = b'/';
+
+/// The entry of a stored key, or of the head of the index.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub(crate) struct IndexEntry {
+    /// The next stored key in byte order, None for the last one.
+    next: Option<Vec<u8>>,
+}
+
+/// The entries written to the index by a commit, by index key. None deletes the entry.
+pub(crate) type IndexUpdates = BTreeMap<Vec<u8>, Option<IndexEntry>>;
+
+/// Reads the index as committed, when committing.
+pub(crate) trait IndexReader {
+    /// Returns the value stored under an index key, proven against the committed root.
+    fn read(&self, index_key: &[u8]) -> anyhow::Result<Option<Vec<u8>>>;
+
+    /// Returns the last stored key ordered before `key` once `updates` are applied, None for the head.
+    fn key_before(&self, key: &[u8], updates: &IndexUpdates) -> anyhow::Result<Option<Vec<u8>>>;
+}
+
+/// Returns the index key of the entry of `key`, or of the head for None.
+pub(crate) fn index_key(key: Option<&[u8]>) -> Vec<u8> {
+    let mut index_key = KEY_INDEX_PREFIX.to_vec();
+    if let Some(key) = key {
+        index_key.push(KEY_INDEX_SEPARATOR);
+        index_key.extend_from_slice(key);
+    }
+    index_key
+}
+
+pub(crate) fn is_index_key(key: &[u8]) -> bool {
+    key.starts_with(KEY_INDEX_PREFIX)
+}
+
+/// Returns the last key added by `updates` before `key`, None for the head.
+/// The outer None means that the updates add no key before `key`.
+pub(crate) fn added_key_before(key: &[u8], updates: &IndexUpdates) -> Option<Option<Vec<u8>>> {
+    updates
+        .range(..index_key(Some(key)))
+        .rev()
+        .find(|(_, entry)| entry.is_some())
+        .map(|(index_key, _)| {
+            (index_key.len() > KEY_INDEX_PREFIX.len())
+                .then(|| index_key[KEY_INDEX_PREFIX.len() + 1..].to_vec())
+        })
+}
+
+/// Returns true if the entry of `key` is deleted by `updates`.
+pub(crate) fn is_removed(key: &[u8], updates: &IndexUpdates) -> bool {
+    matches!(updates.get(&index_key(Some(key))), Some(None))
+}
+
+// The head is written along with the first key, so a missing head is an empty index.
+fn decode_entry(key: Option<&[u8]>, value: Option<&[u8]>) -> Option<IndexEntry> {
+    match value {
+        // It is ok to panic here. Deserialization problem means that something is terribly wrong.
+        Some(mut value) => Some(
+            IndexEntry::deserialize(&mut value)
+                .unwrap_or_else(|e| panic!("Unable to deserialize index entry {e:?}")),
+        ),
+        None if key.is_none() => Some(IndexEntry { next: None }),
+        None => None,
+    }
+}
+
+/// Reads the entries under `prefix`, ordered by key, following the index from `key_before`: the last stored key
+/// ordered before the prefix, None for the head. `get` reads a key, recording the access so that it is proven.
+///
+/// # Panics
+/// If `key_before` isn't a stored key ordered before the prefix.
+pub(crate) fn read_by_prefix(
+    prefix: &Prefix,
+    key_before: Option<StorageKey>,
+    mut get: impl FnMut(StorageKey) -> Option<StorageValue>,
+) -> Vec<(StorageKey, StorageValue)> {
+    let prefix = prefix.as_aligned_vec().as_ref();
+    let key_before = key_before.map(|key| key.as_ref().clone());
+    if let Some(key) = &key_before {
+        assert!(
+            key.as_slice() < prefix.as_slice(),
+            "The key before the prefix must be ordered before it"
+        );
+    }
+
+    let mut read_entry = |key: Option<&[u8]>| {
+        let value = get(StorageKey::new_from_bytes(index_key(key)));
+        decode_entry(key, value.as_ref().map(StorageValue::value))
+    };
+    let mut next = read_entry(key_before.as_deref())
+        .expect("The key before the prefix must be stored")
+        .next;
+
+    let mut keys = Vec::new();
+    while let Some(key) = next {
+        if key.starts_with(prefix) {
+            keys.push(key.clone());
+        } else if key.as_slice() > prefix.as_slice() {
+            break;
+        }
+        next = read_entry(Some(&key))
+            .expect("Stored keys must have an index entry")
+            .next;
+    }
+
+    keys.into_iter()
+        .filter_map(|key| {
+            let key = StorageKey::new_from_bytes(key);
+            get(key.clone()).map(|value| (key, value))
+        })
+        .collect()
+}
+
+/// Updates the index for the `writes` of a commit, given as the written keys with whether they hold a value
+/// afterwards. Returns the values to write under the index keys, None deleting them.
+///
+/// The entries read from the index are proven by the reader, and the key before every added or removed key is
+/// checked against them, so the updates keep the index ordered and complete.
+pub(crate) fn update_index(
+    mut writes: Vec<(Vec<u8>, bool)>,
+    reader: &impl IndexReader,
+) -> anyhow::Result<Vec<(Vec<u8>, Option<Vec<u8>>)>> {
+    writes.sort();
+    let mut updates = IndexUpdates::new();
+
+    for (key, stored) in writes {
+        anyhow::ensure!(
+            !is_index_key(&key),
+            "The key index can't be written directly"
+        );
+        let entry = read_entry(reader, &updates, Some(&key))?;
+        if entry.is_some() == stored {
+            continue;
+        }
+
+        let key_before = reader.key_before(&key, &updates)?;
+        if let Some(key_before) = &key_before {
+            anyhow::ensure!(
+                key_before < &key,
+                "Invalid key before {key:?} in the key index"
+            );
+        }
+        let entry_before = read_entry(reader, &updates, key_before.as_deref())?
+            .ok_or_else(|| anyhow::anyhow!("The key before {key:?} isn't stored"))?;
+
+        match entry {
+            // The key is added between the key before it and the next one.
+            None => {
+                anyhow::ensure!(
+                    entry_before.next.as_ref().map_or(true, |next| next > &key),
+                    "Invalid key before {key:?} in the key index"
+                );
+                updates.insert(index_key(Some(&key)), Some(entry_before));
+                updates.insert(
+                    index_key(key_before.as_deref()),
+                    Some(IndexEntry { next: Some(key) }),
+                );
+            }
+            // The key before it is linked to the next one.
+            Some(entry) => {
+                anyhow::ensure!(
+                    entry_before.next.as_ref() == Some(&key),
+                    "Invalid key before {key:?} in the key index"
+                );
+                updates.insert(index_key(key_before.as_deref()), Some(entry));
+                updates.insert(index_key(Some(&key)), None);
+            }
+        }
+    }
+
+    Ok(updates
+        .into_iter()
+        .map(|(index_key, entry)| {
+            let value = entry.map(|entry| entry.try_to_vec().expect("Serialization is infallible"));
+            (index_key, value)
+        })
+        .collect())
+}
+
+fn read_entry(
+    reader: &impl IndexReader,
+    updates: &IndexUpdates,
+    key: Option<&[u8]>,
+) -> anyhow::Result<Option<IndexEntry>> {
+    let index_key = index_key(key);
+    match updates.get(&index_key) {
+        Some(entry) => Ok(entry.clone()),
+        None => Ok(decode_entry(key, reader.read(&index_key)?.as_deref())),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Reads the index from a map, with a key before which can be wrong.
+    struct TestReader {
+        values: BTreeMap<Vec<u8>, Vec<u8>>,
+        key_before: Option<Vec<u8>>,
+    }
+
+    impl IndexReader for TestReader {
+        fn read(&self, index_key: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+            Ok(self.values.get(index_key).cloned())
+        }
+
+        fn key_before(
+            &self,
+            _key: &[u8],
+            _updates: &IndexUpdates,
+        ) -> anyhow::Result<Option<Vec<u8>>> {
+            Ok(self.key_before.clone())
+        }
+    }
+
+    fn entry(next: Option<&[u8]>) -> Vec<u8> {
+        IndexEntry {
+            next: next.map(|next| next.to_vec()),
+        }
+        .try_to_vec()
+        .unwrap()
+    }
+
+    // The index holds the keys [1] and [3].
+    fn reader(key_before: Option<&[u8]>) -> TestReader {
+        TestReader {
+            values: BTreeMap::from([
+                (index_key(None), entry(Some(&[1]))),
+                (index_key(Some(&[1])), entry(Some(&[3]))),
+                (index_key(Some(&[3])), entry(None)),
+            ]),
+            key_before: key_before.map(|key| key.to_vec()),
+        }
+    }
+
+    #[test]
+    fn test_update_index() {
+        let updates = update_index(vec![(vec![2], true)], &reader(Some(&[1]))).unwrap();
+        assert_eq!(
+            updates,
+            vec![
+                (index_key(Some(&[1])), Some(entry(Some(&[2])))),
+                (index_key(Some(&[2])), Some(entry(Some(&[3])))),
+            ]
+        );
+
+        let updates = update_index(vec![(vec![1], false)], &reader(None)).unwrap();
+        assert_eq!(
+            updates,
+            vec![
+                (index_key(None), Some(entry(Some(&[3])))),
+                (index_key(Some(&[1])), None),
+            ]
+        );
+
+        // Writes which don't add or remove a key leave the index as is.
+        let updates = update_index(vec![(vec![3], true), (vec![4], false)], &reader(None)).unwrap();
+        assert!(updates.is_empty());
+    }
+
+    #[test]
+    fn test_update_index_rejects_wrong_key_before() {
+        // Inserting [2] after the head would drop [1] from the index.
+        assert!(update_index(vec![(vec![2], true)], &reader(None)).is_err());
+        // Inserting [2] after [3] would break the order.
+        assert!(update_index(vec![(vec![2], true)], &reader(Some(&[3]))).is_err());
+        // Removing [3] after the head would drop [1] from the index.
+        assert!(update_index(vec![(vec![3], false)], &reader(None)).is_err());
+        // The index can't be written directly.
+        assert!(update_index(vec![(index_key(Some(&[5])), true)], &reader(None)).is_err());
+    }
+}
@@ -1,4 +1,6 @@
+pub mod gas;
 mod internal_cache;
+mod key_index;
 mod map;
 mod prover_storage;
 mod scratchpad;
@@ -6,20 +8,24 @@ pub mod storage;
 mod tree_db;
 mod utils;
 mod value;
+mod vec;
 mod zk_storage;
 
 #[cfg(test)]
 mod state_tests;
 
 pub use first_read_last_write_cache::cache::CacheLog;
+pub use gas::{GasCosts, GasMeter, GasUsed, OutOfGas};
 pub use map::StateMap;
 pub use prover_storage::{delete_storage, ProverStorage};
 pub use scratchpad::*;
+pub use sovereign_db::pruner::PruningConfig;
 use sovereign_sdk::core::traits::Witness;
 use std::{fmt::Display, str};
 pub use storage::Storage;
 use utils::AlignedVec;
 pub use value::StateValue;
+pub use vec::StateVec;
 pub use zk_storage::ZkStorage;
 
 // A prefix prepended to each key before insertion and retrieval from the storage.
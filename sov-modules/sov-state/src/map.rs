@@ -69,7 +69,66 @@ impl<K: Encode, V: Encode + Decode> StateMap<K, V> {
         working_set.delete_value(self.prefix(), key);
     }
 
+    /// Deletes all the keys from the StateMap.
+    pub fn clear<S: Storage>(&self, working_set: &mut WorkingSet<S>) {
+        for (storage_key, _) in working_set.get_by_prefix(self.prefix()) {
+            working_set.delete(storage_key);
+        }
+    }
+
     pub fn prefix(&self) -> &Prefix {
         &self.prefix
     }
 }
+
+impl<K: Encode + Decode, V: Encode + Decode> StateMap<K, V> {
+    /// Returns an iterator over the entries of the StateMap.
+    /// Entries are ordered by their encoded keys, which is deterministic but, for example,
+    /// does not match the numeric order of integer keys.
+    pub fn iter<S: Storage>(&self, working_set: &mut WorkingSet<S>) -> std::vec::IntoIter<(K, V)> {
+        working_set.get_decoded_by_prefix(self.prefix()).into_iter()
+    }
+
+    /// Returns the keys of the StateMap, in the same order as [`StateMap::iter`].
+    pub fn keys<S: Storage>(&self, working_set: &mut WorkingSet<S>) -> Vec<K> {
+        self.iter(working_set).map(|(key, _)| key).collect()
+    }
+}
+
+/// Maps keyed by pairs model one-to-many relationships, e.g. the balances of every holder of every token.
+/// The encoding of a pair is the encoding of its first element followed by the encoding of the second,
+/// so all the entries sharing the same first element are stored under a common prefix derived from it.
+/// This requires the encoding of `K1` to be prefix-free (e.g. length prefixed), otherwise the entries of
+/// a key whose encoding extends the encoding of another one would be mixed with them.
+impl<K1: Encode, K2: Decode, V: Encode + Decode> StateMap<(K1, K2), V> {
+    /// Returns the entries whose key starts with `key`, as pairs of the second half of the key and the value.
+    /// Entries are ordered like in [`StateMap::iter`].
+    pub fn iter_prefix<S: Storage>(
+        &self,
+        key: &K1,
+        working_set: &mut WorkingSet<S>,
+    ) -> std::vec::IntoIter<(K2, V)> {
+        working_set
+            .get_decoded_by_prefix(&self.nested_prefix(key))
+            .into_iter()
+    }
+
+    /// Deletes all the entries whose key starts with `key`.
+    pub fn clear_prefix<S: Storage>(&self, key: &K1, working_set: &mut WorkingSet<S>) {
+        for (storage_key, _) in working_set.get_by_prefix(&self.nested_prefix(key)) {
+            working_set.delete(storage_key);
+        }
+    }
+
+    fn nested_prefix(&self, key: &K1) -> Prefix {
+        let nested_prefix = StorageKey::new(&self.prefix, key).as_ref().clone();
+        // The storage keys of the pairs start with the nested prefix only if neither the prefix
+        // nor the encoded key are padded.
+        debug_assert_eq!(
+            nested_prefix.len(),
+            self.prefix.len() + key.encode_to_vec().len(),
+            "Storage keys must not be padded"
+        );
+        Prefix::new(nested_prefix)
+    }
+}
@@ -1,17 +1,20 @@
-use std::{fs, path::Path, sync::Arc};
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
 
 use crate::{
+    key_index::{self, IndexReader, IndexUpdates, KEY_INDEX_PREFIX},
     storage::{StorageKey, StorageValue},
     tree_db::TreeReadLogger,
-    Storage, StorageSpec,
+    Prefix, Storage, StorageSpec,
 };
 use first_read_last_write_cache::cache::CacheLog;
-use jmt::{storage::TreeWriter, JellyfishMerkleTree, KeyHash, PhantomHasher, SimpleHasher};
-use sovereign_db::state_db::StateDB;
+use jmt::{JellyfishMerkleTree, KeyHash, PhantomHasher, SimpleHasher, Version};
+use sovereign_db::{pruner::PruningConfig, state_db::StateDB};
 use sovereign_sdk::core::traits::Witness;
 
 pub struct ProverStorage<S: StorageSpec> {
     db: StateDB,
+    /// Set for a read-only view of the state at a past version, see [`ProverStorage::at_version`].
+    pinned_version: Option<Version>,
     _phantom_hasher: PhantomHasher<S::Hasher>,
 }
 
@@ -19,6 +22,7 @@ impl<S: StorageSpec> Clone for ProverStorage<S> {
     fn clone(&self) -> Self {
         Self {
             db: self.db.clone(),
+            pinned_version: self.pinned_version,
             _phantom_hasher: Default::default(),
         }
     }
@@ -36,23 +40,114 @@ impl<S: StorageSpec> ProverStorage<S> {
         Self::with_db(db)
     }
 
+    /// Opens a storage which keeps only the latest `config.retention_window` versions,
+    /// the older ones are pruned in the background.
+    pub fn with_pruning(
+        path: impl AsRef<Path>,
+        config: PruningConfig,
+    ) -> Result<Self, anyhow::Error> {
+        let db = StateDB::with_pruning(&path, config)?;
+        Self::with_db(db)
+    }
+
     fn with_db(db: StateDB) -> Result<Self, anyhow::Error> {
         Ok(Self {
             db,
+            pinned_version: None,
+            _phantom_hasher: Default::default(),
+        })
+    }
+
+    /// Returns the version written by the last commit. Every commit writes a new version:
+    /// the genesis is version 1, and version 0 is the empty state.
+    pub fn latest_version(&self) -> Version {
+        self.db.get_next_version() - 1
+    }
+
+    /// Returns a read-only view of the state as it was right after `version` was committed.
+    /// Commits through the view fail, the view itself can be used in a [`crate::WorkingSet`].
+    ///
+    /// With pruning enabled, reads through the view panic once `version` falls out of the retention window.
+    pub fn at_version(&self, version: Version) -> Result<Self, anyhow::Error> {
+        let latest_version = self.latest_version();
+        anyhow::ensure!(
+            version <= latest_version,
+            "Version {version} is not committed yet, the latest version is {latest_version}"
+        );
+        let oldest_version = self.db.oldest_version();
+        anyhow::ensure!(
+            version >= oldest_version,
+            "Version {version} is pruned, the oldest version is {oldest_version}"
+        );
+        Ok(Self {
+            db: self.db.clone(),
+            pinned_version: Some(version),
             _phantom_hasher: Default::default(),
         })
     }
 
+    // The latest committed version is read unless the storage is pinned. Reading at the next version
+    // returns the same values, as it has no writes yet.
+    fn read_version(&self) -> Version {
+        self.pinned_version
+            .unwrap_or_else(|| self.db.get_next_version())
+    }
+
     fn read_value(&self, key: StorageKey) -> Option<StorageValue> {
         match self
             .db
-            .get_value_option_by_key(self.db.get_next_version(), key.as_ref())
+            .get_value_option_by_key(self.read_version(), key.as_ref())
         {
             Ok(value) => value.map(StorageValue::new_from_bytes),
             // It is ok to panic here, we assume the db is available and consistent.
             Err(e) => panic!("Unable to read value from db: {e}"),
         }
     }
+
+    // The index keys are ordered after the keys of the modules, so the key before them is the last
+    // key of a module when the lookup lands on one.
+    fn read_key_before(&self, version: Version, key: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+        match self.db.get_key_before(version, key)? {
+            Some(key) if key_index::is_index_key(&key) => {
+                self.db.get_key_before(version, KEY_INDEX_PREFIX)
+            }
+            key => Ok(key),
+        }
+    }
+}
+
+/// Reads the key index during a commit, adding the values and proofs of its entries to the witness.
+struct ProverIndexReader<'a, S: StorageSpec> {
+    storage: &'a ProverStorage<S>,
+    witness: &'a S::Witness,
+    latest_version: Version,
+}
+
+impl<'a, S: StorageSpec> IndexReader for ProverIndexReader<'a, S> {
+    fn read(&self, index_key: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+        let key_hash = KeyHash(S::Hasher::hash(index_key));
+        let jmt = JellyfishMerkleTree::<_, S::Hasher>::new(&self.storage.db);
+        let (value, proof) = jmt.get_with_proof(key_hash, self.latest_version)?;
+        self.witness.add_hint(value.clone());
+        self.witness.add_hint(proof);
+        Ok(value)
+    }
+
+    fn key_before(&self, key: &[u8], updates: &IndexUpdates) -> anyhow::Result<Option<Vec<u8>>> {
+        let version = self.storage.db.get_next_version();
+        // The committed keys removed by the updates are skipped, then the keys added by them are considered
+        let mut committed = self.storage.read_key_before(version, key)?;
+        while let Some(key) = committed
+            .clone()
+            .filter(|key| key_index::is_removed(key, updates))
+        {
+            committed = self.storage.read_key_before(version, &key)?;
+        }
+        let added = key_index::added_key_before(key, updates).flatten();
+        let key_before = committed.max(added);
+        self.witness.add_hint(key_before.clone());
+        Ok(key_before)
+    }
 }
 
 impl<S: StorageSpec> Storage for ProverStorage<S> {
@@ -63,12 +158,26 @@ impl<S: StorageSpec> Storage for ProverStorage<S> {
         val
     }
 
+    fn key_before(&self, prefix: &Prefix, witness: &Self::Witness) -> Option<StorageKey> {
+        let key_before =
+            match self.read_key_before(self.read_version(), prefix.as_aligned_vec().as_ref()) {
+                Ok(key) => key.map(StorageKey::new_from_bytes),
+                // It is ok to panic here, we assume the db is available and consistent.
+                Err(e) => panic!("Unable to read key from db: {e}"),
+            };
+        witness.add_hint(key_before.clone());
+        key_before
+    }
+
     fn validate_and_commit(
         &self,
         cache_log: CacheLog,
         witness: &Self::Witness,
     ) -> Result<[u8; 32], anyhow::Error> {
-        let latest_version = self.db.get_next_version() - 1;
+        if let Some(version) = self.pinned_version {
+            anyhow::bail!("The storage is a read-only view of version {version}");
+        }
+        let latest_version = self.latest_version();
         witness.add_hint(latest_version);
 
         let (reads, writes) = cache_log.split();
@@ -87,18 +196,40 @@ impl<S: StorageSpec> Storage for ProverStorage<S> {
             )
         }
 
+        let writes: Vec<_> = writes
+            .into_iter()
+            .map(|(key, value)| {
+                (
+                    Arc::try_unwrap(key.key).unwrap_or_else(|arc| (*arc).clone()),
+                    value.map(|v| Arc::try_unwrap(v.value).unwrap_or_else(|arc| (*arc).clone())),
+                )
+            })
+            .collect();
+        let index_reader = ProverIndexReader {
+            storage: self,
+            witness,
+            latest_version,
+        };
+        let index_updates = key_index::update_index(
+            writes
+                .iter()
+                .map(|(key, value)| (key.clone(), value.is_some()))
+                .collect(),
+            &index_reader,
+        )?;
+
         let tracked_jmt = JellyfishMerkleTree::<_, S::Hasher>::new(&read_logger);
-        // Compute the jmt update from the write batch
-        let batch = writes.into_iter().map(|(key, value)| {
-            let key_hash = KeyHash(S::Hasher::hash(key.key.as_ref()));
-            self.db
-                .put_preimage(key_hash, key.key.as_ref())
-                .expect("preimage must succeed");
-            (
-                key_hash,
-                value.map(|v| Arc::try_unwrap(v.value).unwrap_or_else(|arc| (*arc).clone())),
-            )
-        });
+        // Compute the jmt update from the write batch and the updates of the key index
+        let mut preimages = HashMap::new();
+        let batch: Vec<_> = writes
+            .into_iter()
+            .chain(index_updates)
+            .map(|(key, value)| {
+                let key_hash = KeyHash(S::Hasher::hash(&key));
+                preimages.insert(key_hash, key);
+                (key_hash, value)
+            })
+            .collect();
 
         let next_version = self.db.get_next_version();
 
@@ -106,10 +237,10 @@ impl<S: StorageSpec> Storage for ProverStorage<S> {
             .put_value_set(batch, next_version)
             .expect("JMT update must succeed");
 
+        // The version is written at once, so a crash can't leave it half written
         self.db
-            .write_node_batch(&tree_update.node_batch)
+            .commit_version(next_version, &preimages, &tree_update)
             .expect("db write must succeed");
-        self.db.inc_next_version();
         Ok(new_root.0)
     }
 }
@@ -187,4 +318,107 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_historical_reads() {
+        let prover_storage = ProverStorage::<MockStorageSpec>::temporary();
+        let key = StorageKey::from("key");
+        let values = [StorageValue::from("value_1"), StorageValue::from("value_2")];
+
+        for value in values.iter() {
+            let mut working_set = WorkingSet::new(prover_storage.clone());
+            working_set.set(key.clone(), value.clone());
+            let (cache, witness) = working_set.freeze();
+            prover_storage
+                .validate_and_commit(cache, &witness)
+                .expect("storage is valid");
+        }
+        assert_eq!(prover_storage.latest_version(), 2);
+
+        let view = prover_storage.at_version(0).unwrap();
+        assert!(view.get(key.clone(), &Default::default()).is_none());
+
+        for (version, value) in (1..).zip(values.iter()) {
+            let view = prover_storage.at_version(version).unwrap();
+            let mut working_set = WorkingSet::new(view.clone());
+            assert_eq!(working_set.get(key.clone()).as_ref(), Some(value));
+
+            // The view can't be modified.
+            working_set.set(key.clone(), StorageValue::from("value_3"));
+            let (cache, witness) = working_set.freeze();
+            assert!(view.validate_and_commit(cache, &witness).is_err());
+        }
+        assert_eq!(prover_storage.latest_version(), 2);
+        assert!(prover_storage.at_version(3).is_err());
+    }
+
+    #[test]
+    fn test_pruning() {
+        let path = schemadb::temppath::TempPath::new();
+        let config = PruningConfig {
+            retention_window: 2,
+        };
+        let tests = create_tests();
+        {
+            let prover_storage =
+                ProverStorage::<MockStorageSpec>::with_pruning(&path, config).unwrap();
+            for test in tests.iter() {
+                let mut working_set = WorkingSet::new(prover_storage.clone());
+                // The same key is overwritten, so that its older values become stale.
+                working_set.set(tests[0].key.clone(), test.value.clone());
+                let (cache, witness) = working_set.freeze();
+                prover_storage
+                    .validate_and_commit(cache, &witness)
+                    .expect("storage is valid");
+            }
+            prover_storage.db.wait_for_pruning();
+
+            assert!(prover_storage.at_version(1).is_err());
+            for test in &tests[1..] {
+                let view = prover_storage.at_version(test.version).unwrap();
+                assert_eq!(
+                    view.get(tests[0].key.clone(), &Default::default()),
+                    Some(test.value.clone())
+                );
+            }
+            // The pruned version can't be read anymore.
+            assert!(prover_storage
+                .db
+                .get_value_option_by_key(1, tests[0].key.as_ref())
+                .is_err());
+        }
+
+        // The storage can be reopened with the state it was left at.
+        let prover_storage = ProverStorage::<MockStorageSpec>::with_pruning(&path, config).unwrap();
+        assert_eq!(prover_storage.latest_version(), 3);
+        assert_eq!(
+            prover_storage.get(tests[0].key.clone(), &Default::default()),
+            Some(tests[2].value.clone())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Version 1 is pruned")]
+    fn test_view_of_pruned_version() {
+        let path = schemadb::temppath::TempPath::new();
+        let config = PruningConfig {
+            retention_window: 1,
+        };
+        let tests = create_tests();
+        let prover_storage = ProverStorage::<MockStorageSpec>::with_pruning(&path, config).unwrap();
+        let mut view = None;
+        for test in tests.iter() {
+            let mut working_set = WorkingSet::new(prover_storage.clone());
+            working_set.set(tests[0].key.clone(), test.value.clone());
+            let (cache, witness) = working_set.freeze();
+            prover_storage
+                .validate_and_commit(cache, &witness)
+                .expect("storage is valid");
+            view.get_or_insert_with(|| prover_storage.at_version(1).unwrap());
+        }
+        prover_storage.db.wait_for_pruning();
+
+        // The view was created while its version was readable, it fails rather than reading a pruned state.
+        view.unwrap().get(tests[0].key.clone(), &Default::default());
+    }
 }
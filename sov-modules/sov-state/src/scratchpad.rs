@@ -1,15 +1,13 @@
 use std::fmt::Debug;
 
 use crate::{
+    gas::{GasMeter, OutOfGas},
     internal_cache::StorageInternalCache,
     storage::{StorageKey, StorageValue},
     Prefix, Storage,
 };
-use first_read_last_write_cache::cache::CacheLog;
-use sovereign_sdk::{
-    core::traits::Witness,
-    serial::{Decode, Encode},
-};
+use first_read_last_write_cache::cache::{CacheLog, ValueExists};
+use sovereign_sdk::serial::{Decode, Encode};
 
 /// A working set accumulates reads and writes on top of the underlying DB,
 /// automating witness creation.
@@ -17,22 +15,30 @@ pub struct Delta<S: Storage> {
     inner: S,
     witness: S::Witness,
     cache: StorageInternalCache,
+    // Lives outside of the checkpoints: reverting changes doesn't refund the gas spent on them.
+    gas_meter: GasMeter,
 }
 
 /// A wrapper that adds additional reads and writes on top of an underlying Delta.
 /// These are handly for implementing operations that might revert on top of an existing
 /// working set, without running the risk that the whole working set will be discarded if some particular
 /// operation reverts.
+///
+/// Writes are kept in a stack of checkpoints, so an operation running inside a revertable set can open its own
+/// checkpoint and revert it without affecting the enclosing ones. Reads which miss every checkpoint are recorded
+/// in the underlying Delta together with their witness hints, which keeps the hints in the same order as they
+/// will be consumed by the zk storage.
 pub struct RevertableDelta<S: Storage> {
     inner: Delta<S>,
-    witness: S::Witness,
-    cache: StorageInternalCache,
+    // The last checkpoint is the innermost one. There is always at least one checkpoint.
+    checkpoints: Vec<StorageInternalCache>,
 }
 
 impl<S: Storage> Debug for RevertableDelta<S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("RevertableDelta")
             .field("inner", &self.inner)
+            .field("depth", &self.checkpoints.len())
             .finish()
     }
 }
@@ -52,6 +58,8 @@ impl<S: Storage> WorkingSet<S> {
         Self::Standard(Delta::with_witness(inner, witness))
     }
 
+    /// Makes the working set revertable. A set which is already revertable is returned unchanged,
+    /// use [`WorkingSet::checkpoint`] to open a nested layer.
     pub fn to_revertable(self) -> WorkingSet<S> {
         match self {
             WorkingSet::Standard(delta) => WorkingSet::Revertable(delta.get_revertable_wrapper()),
@@ -59,6 +67,7 @@ impl<S: Storage> WorkingSet<S> {
         }
     }
 
+    /// Commits all the open checkpoints.
     pub fn commit(self) -> Self {
         match self {
             s @ WorkingSet::Standard(_) => s,
@@ -66,6 +75,7 @@ impl<S: Storage> WorkingSet<S> {
         }
     }
 
+    /// Reverts all the open checkpoints.
     pub fn revert(self) -> Self {
         match self {
             s @ WorkingSet::Standard(_) => s,
@@ -73,31 +83,162 @@ impl<S: Storage> WorkingSet<S> {
         }
     }
 
+    /// Opens a new checkpoint. The changes made after this call can be committed or reverted
+    /// independently of the changes made before it.
+    pub fn checkpoint(self) -> Self {
+        match self {
+            WorkingSet::Standard(delta) => WorkingSet::Revertable(delta.get_revertable_wrapper()),
+            WorkingSet::Revertable(mut revertable) => {
+                revertable.push_checkpoint();
+                WorkingSet::Revertable(revertable)
+            }
+        }
+    }
+
+    /// Merges the changes made since the last checkpoint into the enclosing layer.
+    pub fn commit_checkpoint(self) -> Self {
+        match self {
+            s @ WorkingSet::Standard(_) => s,
+            WorkingSet::Revertable(revertable) => revertable.commit_checkpoint(),
+        }
+    }
+
+    /// Discards the changes made since the last checkpoint.
+    pub fn revert_to_checkpoint(self) -> Self {
+        match self {
+            s @ WorkingSet::Standard(_) => s,
+            WorkingSet::Revertable(revertable) => revertable.revert_to_checkpoint(),
+        }
+    }
+
+    /// Runs `f` inside a new checkpoint, which is committed if `f` succeeds and reverted otherwise.
+    pub fn with_checkpoint<T, E>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, E>,
+    ) -> Result<T, E> {
+        self.update(Self::checkpoint);
+        let result = f(self);
+        if result.is_ok() {
+            self.update(Self::commit_checkpoint);
+        } else {
+            self.update(Self::revert_to_checkpoint);
+        }
+        result
+    }
+
+    // Applies a by-value transition to a working set held by reference.
+    fn update(&mut self, transition: impl FnOnce(Self) -> Self) {
+        let placeholder = Self::new(self.backing().clone());
+        let working_set = std::mem::replace(self, placeholder);
+        *self = transition(working_set);
+    }
+
+    /// Returns the number of open checkpoints.
+    pub fn checkpoint_depth(&self) -> usize {
+        match self {
+            WorkingSet::Standard(_) => 0,
+            WorkingSet::Revertable(revertable) => revertable.checkpoints.len(),
+        }
+    }
+
+    /// Replaces the meter charged for the state accesses, returns the previous one.
+    /// A new working set has an unlimited meter.
+    pub fn set_gas_meter(&mut self, gas_meter: GasMeter) -> GasMeter {
+        std::mem::replace(self.gas_meter_mut(), gas_meter)
+    }
+
+    pub fn gas_meter(&self) -> &GasMeter {
+        match self {
+            WorkingSet::Standard(delta) => &delta.gas_meter,
+            WorkingSet::Revertable(revertable) => &revertable.inner.gas_meter,
+        }
+    }
+
+    fn gas_meter_mut(&mut self) -> &mut GasMeter {
+        match self {
+            WorkingSet::Standard(delta) => &mut delta.gas_meter,
+            WorkingSet::Revertable(revertable) => &mut revertable.inner.gas_meter,
+        }
+    }
+
+    /// Charges the base cost of a call to the gas meter.
+    pub fn charge_call(&mut self) -> Result<(), OutOfGas> {
+        self.gas_meter_mut().charge_call()
+    }
+
+    /// Returns None without reading the state once the gas meter is out of gas, see [`WorkingSet::try_get`].
     pub fn get(&mut self, key: StorageKey) -> Option<StorageValue> {
+        self.try_get(key).ok().flatten()
+    }
+
+    /// Reads a value, or returns an error without reading the state once the gas meter is out of gas.
+    pub fn try_get(&mut self, key: StorageKey) -> Result<Option<StorageValue>, OutOfGas> {
+        self.gas_meter_mut().charge_reads(1)?;
         match self {
             WorkingSet::Standard(s) => s.get(key),
             WorkingSet::Revertable(s) => s.get(key),
         }
     }
 
+    /// Has no effect once the gas meter is out of gas, see [`WorkingSet::try_set`].
     pub fn set(&mut self, key: StorageKey, value: StorageValue) {
+        let _ = self.try_set(key, value);
+    }
+
+    /// Writes a value, or returns an error without writing it once the gas meter is out of gas.
+    pub fn try_set(&mut self, key: StorageKey, value: StorageValue) -> Result<(), OutOfGas> {
+        self.gas_meter_mut()
+            .charge_write(key.as_ref().len() + value.value().len())?;
         match self {
             WorkingSet::Standard(s) => s.set(key, value),
             WorkingSet::Revertable(s) => s.set(key, value),
         }
+        Ok(())
     }
 
+    /// Has no effect once the gas meter is out of gas, see [`WorkingSet::try_delete`].
     pub fn delete(&mut self, key: StorageKey) {
+        let _ = self.try_delete(key);
+    }
+
+    /// Deletes a value, or returns an error without deleting it once the gas meter is out of gas.
+    pub fn try_delete(&mut self, key: StorageKey) -> Result<(), OutOfGas> {
+        self.gas_meter_mut().charge_write(0)?;
         match self {
             WorkingSet::Standard(s) => s.delete(key),
             WorkingSet::Revertable(s) => s.delete(key),
         }
+        Ok(())
+    }
+
+    /// Returns all the entries whose key starts with `prefix`, ordered by key.
+    /// Writes which are not committed yet take precedence over the values in the underlying storage.
+    ///
+    /// Returns nothing once the gas meter is out of gas, see [`WorkingSet::try_get_by_prefix`].
+    pub fn get_by_prefix(&mut self, prefix: &Prefix) -> Vec<(StorageKey, StorageValue)> {
+        self.try_get_by_prefix(prefix).unwrap_or_default()
     }
 
+    /// Like [`WorkingSet::get_by_prefix`], but returns an error once the gas meter is out of gas.
+    pub fn try_get_by_prefix(
+        &mut self,
+        prefix: &Prefix,
+    ) -> Result<Vec<(StorageKey, StorageValue)>, OutOfGas> {
+        let entries = match self {
+            WorkingSet::Standard(s) => s.get_by_prefix(prefix)?,
+            WorkingSet::Revertable(s) => s.get_by_prefix(prefix)?,
+        };
+        self.gas_meter_mut().charge_reads(entries.len())?;
+        Ok(entries)
+    }
+
+    /// Extracts the accumulated changes and the witness, leaving the working set empty.
+    /// Open checkpoints are committed implicitly, the set is left with no checkpoint.
     pub fn freeze(&mut self) -> (CacheLog, S::Witness) {
+        self.update(Self::commit);
         match self {
             WorkingSet::Standard(delta) => delta.freeze(),
-            WorkingSet::Revertable(_) => todo!(),
+            WorkingSet::Revertable(_) => unreachable!("Committed sets are standard"),
         }
     }
 
@@ -110,48 +251,116 @@ impl<S: Storage> WorkingSet<S> {
 }
 
 impl<S: Storage> RevertableDelta<S> {
-    fn get(&mut self, key: StorageKey) -> Option<StorageValue> {
-        match self.cache.try_get(key.clone()) {
-            first_read_last_write_cache::cache::ValueExists::Yes(val) => {
-                val.map(StorageValue::new_from_cache_value)
-            }
-            first_read_last_write_cache::cache::ValueExists::No => {
-                self.inner.get_with_witness(key, &self.witness)
+    fn get(&mut self, key: StorageKey) -> Result<Option<StorageValue>, OutOfGas> {
+        for checkpoint in self.checkpoints.iter().rev() {
+            if let ValueExists::Yes(val) = checkpoint.try_get(key.clone()) {
+                return Ok(val.map(StorageValue::new_from_cache_value));
             }
         }
+        self.inner.get(key)
     }
 
     fn set(&mut self, key: StorageKey, value: StorageValue) {
-        self.cache.set(key, value)
+        self.current_checkpoint().set(key, value)
     }
 
     fn delete(&mut self, key: StorageKey) {
-        self.cache.delete(key)
+        self.current_checkpoint().delete(key)
+    }
+
+    fn get_by_prefix(
+        &mut self,
+        prefix: &Prefix,
+    ) -> Result<Vec<(StorageKey, StorageValue)>, OutOfGas> {
+        let entries = self.inner.get_by_prefix(prefix)?;
+        Ok(self
+            .checkpoints
+            .iter()
+            .fold(entries, |entries, checkpoint| {
+                checkpoint.overlay_by_prefix(prefix, entries)
+            }))
+    }
+
+    fn current_checkpoint(&mut self) -> &mut StorageInternalCache {
+        self.checkpoints
+            .last_mut()
+            .expect("RevertableDelta must have at least one checkpoint")
     }
 }
 
 impl<S: Storage> RevertableDelta<S> {
+    fn push_checkpoint(&mut self) {
+        self.checkpoints.push(Default::default());
+    }
+
+    fn commit_checkpoint(mut self) -> WorkingSet<S> {
+        let checkpoint = self
+            .checkpoints
+            .pop()
+            .expect("RevertableDelta must have at least one checkpoint");
+
+        match self.checkpoints.last_mut() {
+            Some(parent) => {
+                parent
+                    .merge_left(checkpoint)
+                    .expect("caches must be consistent");
+                WorkingSet::Revertable(self)
+            }
+            None => {
+                self.inner
+                    .cache
+                    .merge_left(checkpoint)
+                    .expect("caches must be consistent");
+                WorkingSet::Standard(self.inner)
+            }
+        }
+    }
+
+    fn revert_to_checkpoint(mut self) -> WorkingSet<S> {
+        let checkpoint = self
+            .checkpoints
+            .pop()
+            .expect("RevertableDelta must have at least one checkpoint");
+
+        match self.checkpoints.last_mut() {
+            Some(parent) => {
+                parent
+                    .merge_reads_left(checkpoint)
+                    .expect("caches must be consistent");
+                WorkingSet::Revertable(self)
+            }
+            None => {
+                self.inner
+                    .cache
+                    .merge_reads_left(checkpoint)
+                    .expect("caches must be consistent");
+                WorkingSet::Standard(self.inner)
+            }
+        }
+    }
+
     fn commit(self) -> Delta<S> {
         let mut inner = self.inner;
 
-        inner
-            .cache
-            .merge_left(self.cache)
-            .expect("caches must be consistent");
-
-        inner.witness.merge(&self.witness);
+        // Checkpoints are merged from the outermost one, in the order their accesses happened.
+        for checkpoint in self.checkpoints {
+            inner
+                .cache
+                .merge_left(checkpoint)
+                .expect("caches must be consistent");
+        }
         inner
     }
 
     fn revert(self) -> Delta<S> {
         let mut inner = self.inner;
 
-        inner
-            .cache
-            .merge_reads_left(self.cache)
-            .expect("caches must be consistent");
-
-        inner.witness.merge(&self.witness);
+        for checkpoint in self.checkpoints {
+            inner
+                .cache
+                .merge_reads_left(checkpoint)
+                .expect("caches must be consistent");
+        }
         inner
     }
 }
@@ -162,6 +371,7 @@ impl<S: Storage> Delta<S> {
             inner,
             witness: Default::default(),
             cache: Default::default(),
+            gas_meter: Default::default(),
         }
     }
 
@@ -170,18 +380,14 @@ impl<S: Storage> Delta<S> {
             inner,
             witness,
             cache: Default::default(),
+            gas_meter: Default::default(),
         }
     }
 
     fn get_revertable_wrapper(self) -> RevertableDelta<S> {
-        self.get_revertable_wrapper_with_witness(Default::default())
-    }
-
-    fn get_revertable_wrapper_with_witness(self, witness: S::Witness) -> RevertableDelta<S> {
         RevertableDelta {
             inner: self,
-            witness,
-            cache: Default::default(),
+            checkpoints: vec![Default::default()],
         }
     }
 }
@@ -193,8 +399,11 @@ impl<S: Storage> Debug for Delta<S> {
 }
 
 impl<S: Storage> Delta<S> {
-    fn get(&mut self, key: StorageKey) -> Option<StorageValue> {
-        self.cache.get_or_fetch(key, &self.inner, &self.witness)
+    fn get(&mut self, key: StorageKey) -> Result<Option<StorageValue>, OutOfGas> {
+        if !self.cache.contains(&key) {
+            self.gas_meter.charge_witness_hint()?;
+        }
+        Ok(self.cache.get_or_fetch(key, &self.inner, &self.witness))
     }
 
     fn set(&mut self, key: StorageKey, value: StorageValue) {
@@ -204,6 +413,17 @@ impl<S: Storage> Delta<S> {
     fn delete(&mut self, key: StorageKey) {
         self.cache.delete(key)
     }
+
+    fn get_by_prefix(
+        &mut self,
+        prefix: &Prefix,
+    ) -> Result<Vec<(StorageKey, StorageValue)>, OutOfGas> {
+        // The key before the prefix is always fetched from the storage, like a read missing the cache.
+        self.gas_meter.charge_witness_hint()?;
+        Ok(self
+            .cache
+            .get_or_fetch_by_prefix(prefix, &self.inner, &self.witness))
+    }
 }
 
 impl<S: Storage> Delta<S> {
@@ -213,10 +433,6 @@ impl<S: Storage> Delta<S> {
 
         (cache.into(), witness)
     }
-
-    fn get_with_witness(&mut self, key: StorageKey, witness: &S::Witness) -> Option<StorageValue> {
-        self.cache.get_or_fetch(key, &self.inner, witness)
-    }
 }
 
 impl<S: Storage> WorkingSet<S> {
@@ -256,6 +472,24 @@ impl<S: Storage> WorkingSet<S> {
         self.delete(storage_key);
     }
 
+    pub(crate) fn get_decoded_by_prefix<K: Decode, V: Decode>(
+        &mut self,
+        prefix: &Prefix,
+    ) -> Vec<(K, V)> {
+        self.get_by_prefix(prefix)
+            .into_iter()
+            .map(|(storage_key, storage_value)| {
+                let mut encoded_key = &storage_key.as_ref()[prefix.len()..];
+                // It is ok to panic here. Deserialization problem means that something is terribly wrong.
+                let key = K::decode(&mut encoded_key)
+                    .unwrap_or_else(|e| panic!("Unable to deserialize storage key {e:?}"));
+                let value = V::decode(&mut storage_value.value())
+                    .unwrap_or_else(|e| panic!("Unable to deserialize storage value {e:?}"));
+                (key, value)
+            })
+            .collect()
+    }
+
     fn get_decoded<V: Decode>(&mut self, storage_key: StorageKey) -> Option<V> {
         let storage_value = self.get(storage_key)?;
 
@@ -1,7 +1,12 @@
 use std::path::Path;
 
 use super::*;
-use crate::{mocks::MockStorageSpec, ProverStorage};
+use crate::{
+    mocks::MockStorageSpec,
+    storage::{StorageKey, StorageValue},
+    ProverStorage,
+};
+use sovereign_sdk::serial::Encode;
 
 enum Operation {
     Merge,
@@ -165,3 +170,595 @@ fn test_state_value_with_delete() {
         assert!(state_value.get(&mut working_set).is_none());
     }
 }
+
+#[test]
+fn test_state_map_iter() {
+    let path = schemadb::temppath::TempPath::new();
+    for (before_iter, after_iter) in create_storage_operations() {
+        let mut working_set = WorkingSet::new(ProverStorage::with_path(&path).unwrap());
+        let state_map = StateMap::<u32, u32>::new(Prefix::new(vec![1]));
+        let other_map = StateMap::<u32, u32>::new(Prefix::new(vec![2]));
+
+        for key in 1..4 {
+            state_map.set(&key, key * 11, &mut working_set);
+        }
+        other_map.set(&1, 1, &mut working_set);
+
+        working_set = before_iter.execute(working_set);
+        state_map.delete(&2, &mut working_set);
+        state_map.set(&4, 44, &mut working_set);
+
+        let expected = vec![(1, 11), (3, 33), (4, 44)];
+        assert_eq!(
+            state_map.iter(&mut working_set).collect::<Vec<_>>(),
+            expected
+        );
+
+        working_set = after_iter.execute(working_set);
+        assert_eq!(
+            state_map.iter(&mut working_set).collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(state_map.keys(&mut working_set), vec![1, 3, 4]);
+
+        state_map.clear(&mut working_set);
+        assert!(state_map.iter(&mut working_set).next().is_none());
+        assert_eq!(
+            other_map.iter(&mut working_set).collect::<Vec<_>>(),
+            vec![(1, 1)]
+        );
+    }
+}
+
+#[test]
+fn test_state_map_with_pair_keys() {
+    let path = schemadb::temppath::TempPath::new();
+    for (before_iter, after_iter) in create_storage_operations() {
+        let mut working_set = WorkingSet::new(ProverStorage::with_path(&path).unwrap());
+        let state_map = StateMap::<(String, u32), u32>::new(Prefix::new(vec![1]));
+
+        // "a" is a prefix of "ab", but the encoded keys are length prefixed so the entries don't mix.
+        for (name, key) in [("a", 1), ("a", 2), ("ab", 1), ("b", 3)] {
+            state_map.set(&(name.to_owned(), key), key * 11, &mut working_set);
+        }
+
+        working_set = before_iter.execute(working_set);
+        state_map.set(&("a".to_owned(), 3), 33, &mut working_set);
+        state_map.delete(&("a".to_owned(), 1), &mut working_set);
+
+        working_set = after_iter.execute(working_set);
+        assert_eq!(
+            state_map
+                .iter_prefix(&"a".to_owned(), &mut working_set)
+                .collect::<Vec<_>>(),
+            vec![(2, 22), (3, 33)]
+        );
+        assert_eq!(
+            state_map
+                .iter_prefix(&"ab".to_owned(), &mut working_set)
+                .collect::<Vec<_>>(),
+            vec![(1, 11)]
+        );
+
+        state_map.clear_prefix(&"a".to_owned(), &mut working_set);
+        assert!(state_map
+            .iter_prefix(&"a".to_owned(), &mut working_set)
+            .next()
+            .is_none());
+        assert_eq!(
+            state_map.iter(&mut working_set).collect::<Vec<_>>(),
+            vec![(("b".to_owned(), 3), 33), (("ab".to_owned(), 1), 11)]
+        );
+    }
+}
+
+#[test]
+fn test_pair_keys_start_with_the_first_key() {
+    // Lengths which aren't a multiple of 4, so a padded storage key would break the prefix.
+    let prefix = Prefix::new(vec![1, 2, 3]);
+    let first_key = "abc".to_owned();
+
+    let nested_prefix = StorageKey::new(&prefix, &first_key);
+    let pair_key = StorageKey::new(&prefix, &(first_key.clone(), 7u32));
+
+    let mut expected = vec![1, 2, 3];
+    first_key.encode(&mut expected);
+    assert_eq!(nested_prefix.as_ref(), &expected);
+    assert!(pair_key.as_ref().starts_with(&expected));
+}
+
+#[test]
+fn test_pair_keys_zk() {
+    let prover_storage = ProverStorage::<MockStorageSpec>::temporary();
+    let state_map = StateMap::<(String, u32), u32>::new(Prefix::new(vec![1]));
+
+    let genesis_root = {
+        let mut working_set = WorkingSet::new(prover_storage.clone());
+        for (name, key) in [("a", 1), ("a", 2), ("ab", 1), ("b", 3)] {
+            state_map.set(&(name.to_owned(), key), key * 11, &mut working_set);
+        }
+        let (cache_log, witness) = working_set.freeze();
+        prover_storage
+            .validate_and_commit(cache_log, &witness)
+            .expect("JMT update is valid")
+    };
+
+    fn execute<S: Storage>(
+        state_map: &StateMap<(String, u32), u32>,
+        mut working_set: WorkingSet<S>,
+    ) -> WorkingSet<S> {
+        state_map.set(&("a".to_owned(), 3), 33, &mut working_set);
+        assert_eq!(
+            state_map
+                .iter_prefix(&"a".to_owned(), &mut working_set)
+                .collect::<Vec<_>>(),
+            vec![(1, 11), (2, 22), (3, 33)]
+        );
+        state_map.clear_prefix(&"ab".to_owned(), &mut working_set);
+        assert!(state_map
+            .iter_prefix(&"ab".to_owned(), &mut working_set)
+            .next()
+            .is_none());
+        working_set
+    }
+
+    let (native_root, witness) = {
+        let working_set = WorkingSet::new(prover_storage.clone());
+        let mut working_set = execute(&state_map, working_set);
+        let (cache_log, witness) = working_set.freeze();
+        let root = prover_storage
+            .validate_and_commit(cache_log, &witness)
+            .expect("JMT update is valid");
+        (root, witness)
+    };
+
+    let zk_storage = ZkStorage::<MockStorageSpec>::new(genesis_root);
+    let working_set = WorkingSet::with_witness(zk_storage.clone(), witness);
+    let mut working_set = execute(&state_map, working_set);
+    let (cache_log, witness) = working_set.freeze();
+    let zk_root = zk_storage
+        .validate_and_commit(cache_log, &witness)
+        .expect("JMT update is valid");
+    assert_eq!(native_root, zk_root);
+
+    let mut working_set = WorkingSet::new(prover_storage);
+    assert_eq!(
+        state_map.iter(&mut working_set).collect::<Vec<_>>(),
+        vec![
+            (("a".to_owned(), 1), 11),
+            (("a".to_owned(), 2), 22),
+            (("a".to_owned(), 3), 33),
+            (("b".to_owned(), 3), 33),
+        ]
+    );
+}
+
+#[test]
+fn test_state_map_iter_with_revert() {
+    let mut working_set = WorkingSet::new(ProverStorage::<MockStorageSpec>::temporary());
+    let state_map = StateMap::<u32, u32>::new(Prefix::new(vec![1]));
+    state_map.set(&1, 11, &mut working_set);
+
+    working_set = working_set.to_revertable();
+    state_map.set(&2, 22, &mut working_set);
+    state_map.delete(&1, &mut working_set);
+    assert_eq!(
+        state_map.iter(&mut working_set).collect::<Vec<_>>(),
+        vec![(2, 22)]
+    );
+
+    working_set = working_set.revert();
+    assert_eq!(
+        state_map.iter(&mut working_set).collect::<Vec<_>>(),
+        vec![(1, 11)]
+    );
+}
+
+#[test]
+fn test_state_map_iter_zk() {
+    let prover_storage = ProverStorage::<MockStorageSpec>::temporary();
+    let state_value = StateValue::<u32>::new(Prefix::new(vec![0]));
+    let state_map = StateMap::<u32, u32>::new(Prefix::new(vec![1]));
+    let other_map = StateMap::<u32, u32>::new(Prefix::new(vec![2]));
+
+    let commit = |f: &dyn Fn(&mut WorkingSet<ProverStorage<MockStorageSpec>>)| {
+        let mut working_set = WorkingSet::new(prover_storage.clone());
+        f(&mut working_set);
+        let (cache_log, witness) = working_set.freeze();
+        prover_storage
+            .validate_and_commit(cache_log, &witness)
+            .expect("JMT update is valid")
+    };
+    commit(&|working_set| {
+        state_value.set(1, working_set);
+        for key in 1..=3 {
+            state_map.set(&key, key * 11, working_set);
+        }
+        other_map.set(&1, 11, working_set);
+    });
+    let prev_root = commit(&|working_set| {
+        state_map.delete(&2, working_set);
+        state_map.set(&4, 44, working_set);
+    });
+
+    fn execute<S: Storage>(
+        state_map: &StateMap<u32, u32>,
+        mut working_set: WorkingSet<S>,
+    ) -> WorkingSet<S> {
+        assert_eq!(
+            state_map.iter(&mut working_set).collect::<Vec<_>>(),
+            vec![(1, 11), (3, 33), (4, 44)]
+        );
+        state_map.set(&5, 55, &mut working_set);
+        state_map.delete(&1, &mut working_set);
+        assert_eq!(state_map.keys(&mut working_set), vec![3, 4, 5]);
+        working_set
+    }
+
+    let (native_root, witness) = {
+        let working_set = WorkingSet::new(prover_storage.clone());
+        let mut working_set = execute(&state_map, working_set);
+        let (cache_log, witness) = working_set.freeze();
+        let root = prover_storage
+            .validate_and_commit(cache_log, &witness)
+            .expect("JMT update is valid");
+        (root, witness)
+    };
+
+    // The entries are read from the key index, whose entries are proven against the previous root.
+    let zk_storage = ZkStorage::<MockStorageSpec>::new(prev_root);
+    let working_set = WorkingSet::with_witness(zk_storage.clone(), witness);
+    let mut working_set = execute(&state_map, working_set);
+    let (cache_log, witness) = working_set.freeze();
+    let zk_root = zk_storage
+        .validate_and_commit(cache_log, &witness)
+        .expect("JMT update is valid");
+    assert_eq!(native_root, zk_root);
+
+    // The key index is updated with the committed writes.
+    let mut working_set = WorkingSet::new(prover_storage.clone());
+    assert_eq!(
+        state_map.iter(&mut working_set).collect::<Vec<_>>(),
+        vec![(3, 33), (4, 44), (5, 55)]
+    );
+    assert_eq!(
+        other_map.iter(&mut working_set).collect::<Vec<_>>(),
+        vec![(1, 11)]
+    );
+}
+
+fn create_state_vec_and_storage(
+    values: Vec<u32>,
+    path: impl AsRef<Path>,
+) -> (StateVec<u32>, WorkingSet<ProverStorage<MockStorageSpec>>) {
+    let mut working_set = WorkingSet::new(ProverStorage::with_path(&path).unwrap());
+
+    let state_vec = StateVec::new(Prefix::new(vec![0]));
+    for value in values {
+        state_vec.push(value, &mut working_set);
+    }
+    (state_vec, working_set)
+}
+
+#[test]
+fn test_state_vec_push_and_pop() {
+    for (before_pop, after_pop) in create_storage_operations() {
+        let path = schemadb::temppath::TempPath::new();
+        let (state_vec, mut working_set) = create_state_vec_and_storage(vec![1, 2, 3], &path);
+
+        working_set = before_pop.execute(working_set);
+        assert_eq!(state_vec.len(&mut working_set), 3);
+        assert_eq!(state_vec.pop(&mut working_set), Some(3));
+
+        working_set = after_pop.execute(working_set);
+        assert_eq!(state_vec.len(&mut working_set), 2);
+        assert_eq!(state_vec.get(1, &mut working_set), Some(2));
+        assert!(state_vec.get(2, &mut working_set).is_none());
+        assert_eq!(
+            state_vec
+                .iter(&mut working_set)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            vec![1, 2]
+        );
+    }
+}
+
+#[test]
+fn test_state_vec_iter_missing_element() {
+    let path = schemadb::temppath::TempPath::new();
+    let (state_vec, mut working_set) = create_state_vec_and_storage(vec![1, 2, 3], &path);
+    working_set.delete_value(state_vec.prefix(), &1u64);
+
+    let mut iter = state_vec.iter(&mut working_set);
+    assert_eq!(iter.next().unwrap().unwrap(), 1);
+    assert!(iter.next().unwrap().is_err());
+    assert_eq!(iter.next().unwrap().unwrap(), 3);
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn test_state_vec_set_and_truncate() {
+    for (before_truncate, after_truncate) in create_storage_operations() {
+        let path = schemadb::temppath::TempPath::new();
+        let (state_vec, mut working_set) = create_state_vec_and_storage(vec![1, 2, 3], &path);
+
+        state_vec.set(0, 11, &mut working_set).unwrap();
+        assert!(state_vec.set(3, 44, &mut working_set).is_err());
+
+        working_set = before_truncate.execute(working_set);
+        state_vec.truncate(1, &mut working_set);
+
+        working_set = after_truncate.execute(working_set);
+        assert_eq!(
+            state_vec
+                .iter(&mut working_set)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            vec![11]
+        );
+        assert!(state_vec.get_or_err(1, &mut working_set).is_err());
+
+        state_vec.clear(&mut working_set);
+        assert!(state_vec.is_empty(&mut working_set));
+        assert!(state_vec.pop(&mut working_set).is_none());
+    }
+}
+
+#[test]
+fn test_nested_checkpoints() {
+    let mut working_set = WorkingSet::new(ProverStorage::<MockStorageSpec>::temporary());
+    let state_map = StateMap::<u32, u32>::new(Prefix::new(vec![1]));
+    state_map.set(&1, 11, &mut working_set);
+
+    working_set = working_set.checkpoint();
+    state_map.set(&2, 22, &mut working_set);
+
+    working_set = working_set.checkpoint();
+    assert_eq!(working_set.checkpoint_depth(), 2);
+    state_map.set(&1, 111, &mut working_set);
+    state_map.set(&3, 33, &mut working_set);
+    assert_eq!(state_map.get(&1, &mut working_set), Some(111));
+
+    // Reverting the inner checkpoint keeps the changes of the outer one.
+    working_set = working_set.revert_to_checkpoint();
+    assert_eq!(working_set.checkpoint_depth(), 1);
+    assert_eq!(
+        state_map.iter(&mut working_set).collect::<Vec<_>>(),
+        vec![(1, 11), (2, 22)]
+    );
+
+    working_set = working_set.checkpoint();
+    state_map.delete(&1, &mut working_set);
+    working_set = working_set.commit_checkpoint();
+    assert_eq!(working_set.checkpoint_depth(), 1);
+    assert!(state_map.get(&1, &mut working_set).is_none());
+
+    // Reverting the outer checkpoint discards the committed inner one as well.
+    working_set = working_set.revert_to_checkpoint();
+    assert_eq!(working_set.checkpoint_depth(), 0);
+    assert_eq!(
+        state_map.iter(&mut working_set).collect::<Vec<_>>(),
+        vec![(1, 11)]
+    );
+}
+
+#[test]
+fn test_with_checkpoint() {
+    let mut working_set =
+        WorkingSet::new(ProverStorage::<MockStorageSpec>::temporary()).to_revertable();
+    let state_value = StateValue::<u32>::new(Prefix::new(vec![1]));
+    state_value.set(1, &mut working_set);
+
+    let result: Result<(), ()> = working_set.with_checkpoint(|working_set| {
+        assert_eq!(working_set.checkpoint_depth(), 2);
+        state_value.set(2, working_set);
+        Err(())
+    });
+    assert!(result.is_err());
+    assert_eq!(working_set.checkpoint_depth(), 1);
+    assert_eq!(state_value.get(&mut working_set), Some(1));
+
+    let result: Result<u32, ()> = working_set.with_checkpoint(|working_set| {
+        state_value.set(3, working_set);
+        Ok(3)
+    });
+    assert_eq!(result, Ok(3));
+    assert_eq!(working_set.checkpoint_depth(), 1);
+    assert_eq!(state_value.get(&mut working_set), Some(3));
+
+    // The changes made before the checkpoints are still revertable.
+    working_set = working_set.revert();
+    assert!(state_value.get(&mut working_set).is_none());
+}
+
+#[test]
+fn test_gas_metering() {
+    let mut working_set = WorkingSet::new(ProverStorage::<MockStorageSpec>::temporary());
+    let state_value = StateValue::<u32>::new(Prefix::new(vec![1]));
+    let costs = GasCosts::default();
+    let previous = working_set.set_gas_meter(GasMeter::new(1_000, costs.clone()));
+    assert_eq!(previous, GasMeter::unlimited());
+
+    // The first read adds a witness hint, the following ones hit the cache.
+    assert!(state_value.get(&mut working_set).is_none());
+    assert!(state_value.get(&mut working_set).is_none());
+    let used = working_set.gas_meter().used();
+    assert_eq!(used.compute, 2 * costs.read + costs.witness_hint);
+    assert_eq!(used.storage, 0);
+
+    // Reverted writes are charged as well.
+    working_set = working_set.to_revertable();
+    state_value.set(1, &mut working_set);
+    working_set = working_set.revert();
+    let used = working_set.gas_meter().used();
+    assert_eq!(
+        used.compute,
+        2 * costs.read + costs.witness_hint + costs.write
+    );
+    assert!(used.storage > 0);
+    assert!(working_set.gas_meter().check().is_ok());
+
+    working_set.set_gas_meter(GasMeter::new(costs.read, costs.clone()));
+    state_value.get(&mut working_set);
+    assert!(working_set.gas_meter().check().is_ok());
+    state_value.get(&mut working_set);
+    assert_eq!(
+        working_set.gas_meter().check(),
+        Err(OutOfGas {
+            used: 2 * costs.read,
+            limit: costs.read
+        })
+    );
+}
+
+#[test]
+fn test_out_of_gas_stops_state_accesses() {
+    let mut working_set = WorkingSet::new(ProverStorage::<MockStorageSpec>::temporary());
+    let state_map = StateMap::<u32, u32>::new(Prefix::new(vec![1]));
+    let costs = GasCosts::default();
+    // Enough for a handful of writes only.
+    working_set.set_gas_meter(GasMeter::new(10 * costs.write, costs.clone()));
+
+    for key in 0..10_000 {
+        state_map.set(&key, key, &mut working_set);
+    }
+    assert!(working_set.gas_meter().check().is_err());
+
+    // The accesses stopped once the gas ran out.
+    assert!(state_map.get(&0, &mut working_set).is_none());
+    let (cache_log, _) = working_set.freeze();
+    assert!(cache_log.len() < 10);
+}
+
+#[test]
+fn test_try_accesses_return_out_of_gas() {
+    let mut working_set = WorkingSet::new(ProverStorage::<MockStorageSpec>::temporary());
+    let costs = GasCosts::default();
+    working_set.set_gas_meter(GasMeter::new(
+        costs.read + costs.witness_hint,
+        costs.clone(),
+    ));
+    let key = StorageKey::new_from_bytes(vec![1]);
+    let prefix = Prefix::new(vec![1]);
+
+    assert_eq!(working_set.try_get(key.clone()), Ok(None));
+    let out_of_gas = working_set
+        .try_set(key.clone(), StorageValue::new(1u32))
+        .unwrap_err();
+    assert_eq!(out_of_gas.limit, costs.read + costs.witness_hint);
+    assert!(out_of_gas.used > out_of_gas.limit);
+
+    assert!(working_set.try_get(key.clone()).is_err());
+    assert!(working_set.try_delete(key).is_err());
+    assert!(working_set.try_get_by_prefix(&prefix).is_err());
+}
+
+#[test]
+fn test_nested_checkpoints_zk() {
+    let path = schemadb::temppath::TempPath::new();
+    let prover_storage = ProverStorage::<MockStorageSpec>::with_path(&path).unwrap();
+    let state_value = StateValue::<u32>::new(Prefix::new(vec![1]));
+    let state_map = StateMap::<u32, u32>::new(Prefix::new(vec![2]));
+
+    let genesis_root = {
+        let mut working_set = WorkingSet::new(prover_storage.clone());
+        state_value.set(1, &mut working_set);
+        state_map.set(&1, 11, &mut working_set);
+        let (cache_log, witness) = working_set.freeze();
+        prover_storage
+            .validate_and_commit(cache_log, &witness)
+            .expect("JMT update is valid")
+    };
+
+    fn execute<S: Storage>(
+        state_value: &StateValue<u32>,
+        state_map: &StateMap<u32, u32>,
+        mut working_set: WorkingSet<S>,
+    ) -> WorkingSet<S> {
+        working_set = working_set.checkpoint();
+        assert_eq!(state_value.get(&mut working_set), Some(1));
+        state_value.set(2, &mut working_set);
+
+        working_set = working_set.checkpoint();
+        assert_eq!(state_map.get(&1, &mut working_set), Some(11));
+        assert!(state_map.get(&2, &mut working_set).is_none());
+        state_map.set(&2, 22, &mut working_set);
+        working_set = working_set.revert_to_checkpoint();
+
+        working_set.commit_checkpoint()
+    }
+
+    let (native_root, witness) = {
+        let working_set = WorkingSet::new(prover_storage.clone());
+        let mut working_set = execute(&state_value, &state_map, working_set);
+        let (cache_log, witness) = working_set.freeze();
+        let root = prover_storage
+            .validate_and_commit(cache_log, &witness)
+            .expect("JMT update is valid");
+        (root, witness)
+    };
+
+    let zk_storage = ZkStorage::<MockStorageSpec>::new(genesis_root);
+    let working_set = WorkingSet::with_witness(zk_storage.clone(), witness);
+    let mut working_set = execute(&state_value, &state_map, working_set);
+    let (cache_log, witness) = working_set.freeze();
+    let zk_root = zk_storage
+        .validate_and_commit(cache_log, &witness)
+        .expect("JMT update is valid");
+
+    assert_eq!(native_root, zk_root);
+}
+
+#[test]
+fn test_freeze_after_commit() {
+    let path = schemadb::temppath::TempPath::new();
+    let prover_storage = ProverStorage::<MockStorageSpec>::with_path(&path).unwrap();
+    let state_map = StateMap::<u32, u32>::new(Prefix::new(vec![1]));
+
+    let mut working_set = WorkingSet::new(prover_storage.clone());
+    state_map.set(&1, 11, &mut working_set);
+    working_set = working_set.checkpoint();
+    state_map.set(&2, 22, &mut working_set);
+    working_set = working_set.checkpoint();
+    state_map.set(&1, 111, &mut working_set);
+
+    let mut working_set = working_set.commit();
+    assert_eq!(working_set.checkpoint_depth(), 0);
+    let (cache_log, witness) = working_set.freeze();
+    prover_storage
+        .validate_and_commit(cache_log, &witness)
+        .expect("JMT update is valid");
+
+    let mut working_set =
+        WorkingSet::new(ProverStorage::<MockStorageSpec>::with_path(&path).unwrap());
+    assert_eq!(
+        state_map.iter(&mut working_set).collect::<Vec<_>>(),
+        vec![(1, 111), (2, 22)]
+    );
+}
+
+#[test]
+fn test_freeze_with_open_checkpoints() {
+    let path = schemadb::temppath::TempPath::new();
+    let prover_storage = ProverStorage::<MockStorageSpec>::with_path(&path).unwrap();
+    let state_map = StateMap::<u32, u32>::new(Prefix::new(vec![1]));
+
+    let mut working_set = WorkingSet::new(prover_storage.clone());
+    working_set = working_set.checkpoint();
+    state_map.set(&1, 11, &mut working_set);
+    working_set = working_set.checkpoint();
+    state_map.set(&2, 22, &mut working_set);
+
+    // The open checkpoints are committed.
+    let (cache_log, witness) = working_set.freeze();
+    assert_eq!(working_set.checkpoint_depth(), 0);
+    prover_storage
+        .validate_and_commit(cache_log, &witness)
+        .expect("JMT update is valid");
+
+    let mut working_set =
+        WorkingSet::new(ProverStorage::<MockStorageSpec>::with_path(&path).unwrap());
+    assert_eq!(state_map.get(&1, &mut working_set), Some(11));
+    assert_eq!(state_map.get(&2, &mut working_set), Some(22));
+}
@@ -7,7 +7,7 @@ use hex;
 use sovereign_sdk::{core::traits::Witness, serial::Encode};
 
 // `Key` type for the `Storage`
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug, BorshSerialize, BorshDeserialize)]
 pub struct StorageKey {
     key: Arc<Vec<u8>>,
 }
@@ -20,6 +20,14 @@ impl StorageKey {
     pub fn as_cache_key(self) -> CacheKey {
         CacheKey { key: self.key }
     }
+
+    pub fn new_from_cache_key(cache_key: CacheKey) -> Self {
+        Self { key: cache_key.key }
+    }
+
+    pub fn new_from_bytes(key: Vec<u8>) -> Self {
+        Self { key: Arc::new(key) }
+    }
 }
 
 impl AsRef<Vec<u8>> for StorageKey {
@@ -95,6 +103,10 @@ pub trait Storage: Clone {
     /// Returns the value corresponding to the key or None if key is absent.
     fn get(&self, key: StorageKey, witness: &Self::Witness) -> Option<StorageValue>;
 
+    /// Returns the last stored key ordered before the prefix, or None if there is none.
+    /// The entries under the prefix are read from it by following the key index, which proves that none is omitted.
+    fn key_before(&self, prefix: &Prefix, witness: &Self::Witness) -> Option<StorageKey>;
+
     /// Validate all of the storage accesses in a particular cache log,
     /// returning the new state root after applying all writes
     fn validate_and_commit(
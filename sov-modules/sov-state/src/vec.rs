@@ -0,0 +1,186 @@
+use std::marker::PhantomData;
+
+use crate::{value::SingletonKey, Prefix, Storage, WorkingSet};
+use sovereign_sdk::serial::{Decode, Encode};
+use thiserror::Error;
+
+/// A growable array of values. The length and every element are stored under separate keys,
+/// so reading or writing an element touches a single storage slot.
+#[derive(borsh::BorshDeserialize, borsh::BorshSerialize, Debug, PartialEq, Eq, Clone)]
+pub struct StateVec<V> {
+    _phantom: PhantomData<V>,
+    prefix: Prefix,
+}
+
+/// Error type for `StateVec` methods.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Index {index} out of bounds for prefix: {prefix} and length: {len}")]
+    IndexOutOfBounds {
+        prefix: Prefix,
+        index: usize,
+        len: usize,
+    },
+    #[error("Value not found for prefix: {0} and index: {1}")]
+    MissingValue(Prefix, usize),
+}
+
+impl<V: Encode + Decode> StateVec<V> {
+    pub fn new(prefix: Prefix) -> Self {
+        Self {
+            _phantom: PhantomData,
+            prefix,
+        }
+    }
+
+    /// Returns the number of elements in the StateVec.
+    pub fn len<S: Storage>(&self, working_set: &mut WorkingSet<S>) -> usize {
+        let len: u64 = working_set
+            .get_value(self.prefix(), &SingletonKey)
+            .unwrap_or_default();
+        len as usize
+    }
+
+    /// Returns true if the StateVec contains no elements.
+    pub fn is_empty<S: Storage>(&self, working_set: &mut WorkingSet<S>) -> bool {
+        self.len(working_set) == 0
+    }
+
+    /// Returns the element at the given index or None if the index is out of bounds.
+    pub fn get<S: Storage>(&self, index: usize, working_set: &mut WorkingSet<S>) -> Option<V> {
+        if index >= self.len(working_set) {
+            return None;
+        }
+        working_set.get_value(self.prefix(), &(index as u64))
+    }
+
+    /// Returns the element at the given index or Error if the index is out of bounds.
+    pub fn get_or_err<S: Storage>(
+        &self,
+        index: usize,
+        working_set: &mut WorkingSet<S>,
+    ) -> Result<V, Error> {
+        self.check_bounds(index, working_set)?;
+        working_set
+            .get_value(self.prefix(), &(index as u64))
+            .ok_or_else(|| Error::MissingValue(self.prefix().clone(), index))
+    }
+
+    /// Overwrites the element at the given index, returning Error if the index is out of bounds.
+    pub fn set<S: Storage>(
+        &self,
+        index: usize,
+        value: V,
+        working_set: &mut WorkingSet<S>,
+    ) -> Result<(), Error> {
+        self.check_bounds(index, working_set)?;
+        working_set.set_value(self.prefix(), &(index as u64), value);
+        Ok(())
+    }
+
+    /// Appends an element to the back of the StateVec.
+    pub fn push<S: Storage>(&self, value: V, working_set: &mut WorkingSet<S>) {
+        let len = self.len(working_set);
+        working_set.set_value(self.prefix(), &(len as u64), value);
+        self.set_len(len + 1, working_set);
+    }
+
+    /// Removes the last element from the StateVec and returns it (or None if the StateVec is empty).
+    pub fn pop<S: Storage>(&self, working_set: &mut WorkingSet<S>) -> Option<V> {
+        let len = self.len(working_set);
+        let last_index = len.checked_sub(1)?;
+        let value = working_set.remove_value(self.prefix(), &(last_index as u64));
+        self.set_len(last_index, working_set);
+        value
+    }
+
+    /// Shortens the StateVec to `len` elements, deleting the rest.
+    /// Has no effect if `len` is greater than or equal to the current length.
+    pub fn truncate<S: Storage>(&self, len: usize, working_set: &mut WorkingSet<S>) {
+        let current_len = self.len(working_set);
+        if len >= current_len {
+            return;
+        }
+
+        for index in len..current_len {
+            working_set.delete_value(self.prefix(), &(index as u64));
+        }
+        self.set_len(len, working_set);
+    }
+
+    /// Deletes all the elements from the StateVec.
+    pub fn clear<S: Storage>(&self, working_set: &mut WorkingSet<S>) {
+        self.truncate(0, working_set);
+    }
+
+    /// Returns an iterator over the elements of the StateVec. Elements are read lazily,
+    /// one storage access at a time. An element missing below the length is returned as
+    /// [`Error::MissingValue`].
+    pub fn iter<'a, 'ws, S: Storage>(
+        &'a self,
+        working_set: &'ws mut WorkingSet<S>,
+    ) -> StateVecIter<'a, 'ws, V, S> {
+        let len = self.len(working_set);
+        StateVecIter {
+            state_vec: self,
+            working_set,
+            len,
+            next_index: 0,
+        }
+    }
+
+    pub fn prefix(&self) -> &Prefix {
+        &self.prefix
+    }
+
+    fn set_len<S: Storage>(&self, len: usize, working_set: &mut WorkingSet<S>) {
+        working_set.set_value(self.prefix(), &SingletonKey, len as u64);
+    }
+
+    fn check_bounds<S: Storage>(
+        &self,
+        index: usize,
+        working_set: &mut WorkingSet<S>,
+    ) -> Result<(), Error> {
+        let len = self.len(working_set);
+        if index >= len {
+            return Err(Error::IndexOutOfBounds {
+                prefix: self.prefix().clone(),
+                index,
+                len,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// An iterator over the elements of a `StateVec`, see [`StateVec::iter`].
+pub struct StateVecIter<'a, 'ws, V, S: Storage> {
+    state_vec: &'a StateVec<V>,
+    working_set: &'ws mut WorkingSet<S>,
+    len: usize,
+    next_index: usize,
+}
+
+impl<'a, 'ws, V: Encode + Decode, S: Storage> Iterator for StateVecIter<'a, 'ws, V, S> {
+    type Item = Result<V, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next_index >= self.len {
+            return None;
+        }
+
+        let index = self.next_index;
+        self.next_index += 1;
+        let value = self
+            .working_set
+            .get_value(self.state_vec.prefix(), &(index as u64))
+            .ok_or_else(|| Error::MissingValue(self.state_vec.prefix().clone(), index));
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.next_index;
+        (remaining, Some(remaining))
+    }
+}
@@ -5,8 +5,9 @@ use jmt::{JellyfishMerkleTree, KeyHash, PhantomHasher, SimpleHasher, Version};
 use sovereign_sdk::core::traits::{TreeWitnessReader, Witness};
 
 use crate::{
+    key_index::{self, IndexReader, IndexUpdates},
     storage::{StorageKey, StorageValue},
-    Storage, StorageSpec,
+    Prefix, Storage, StorageSpec,
 };
 
 pub struct ZkStorage<S: StorageSpec> {
@@ -32,11 +33,40 @@ impl<S: StorageSpec> ZkStorage<S> {
     }
 }
 
+/// Reads the key index during a commit from the witness, verifying its entries against the previous root.
+struct ZkIndexReader<'a, S: StorageSpec> {
+    storage: &'a ZkStorage<S>,
+    witness: &'a S::Witness,
+}
+
+impl<'a, S: StorageSpec> IndexReader for ZkIndexReader<'a, S> {
+    fn read(&self, index_key: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+        let key_hash = KeyHash(S::Hasher::hash(index_key));
+        let value: Option<Vec<u8>> = self.witness.get_hint();
+        let proof: jmt::proof::SparseMerkleProof<S::Hasher> = self.witness.get_hint();
+        let root = jmt::RootHash(self.storage.prev_state_root);
+        match &value {
+            Some(value) => proof.verify_existence(root, key_hash, value)?,
+            None => proof.verify_nonexistence(root, key_hash)?,
+        }
+        Ok(value)
+    }
+
+    // The hinted key is checked against the entries of the index by `update_index`.
+    fn key_before(&self, _key: &[u8], _updates: &IndexUpdates) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(self.witness.get_hint())
+    }
+}
+
 impl<S: StorageSpec> Storage for ZkStorage<S> {
     fn get(&self, _key: StorageKey, witness: &S::Witness) -> Option<StorageValue> {
         witness.get_hint()
     }
 
+    fn key_before(&self, _prefix: &Prefix, witness: &S::Witness) -> Option<StorageKey> {
+        witness.get_hint()
+    }
+
     fn validate_and_commit(
         &self,
         cache_log: cache::CacheLog,
@@ -61,14 +91,32 @@ impl<S: StorageSpec> Storage for ZkStorage<S> {
             }
         }
 
-        // Compute the jmt update from the write batch
-        let batch = writes.into_iter().map(|(key, value)| {
-            let key_hash = KeyHash(S::Hasher::hash(key.key.as_ref()));
-            (
-                key_hash,
-                value.map(|v| Arc::try_unwrap(v.value).unwrap_or_else(|arc| (*arc).clone())),
-            )
-        });
+        let writes: Vec<_> = writes
+            .into_iter()
+            .map(|(key, value)| {
+                (
+                    Arc::try_unwrap(key.key).unwrap_or_else(|arc| (*arc).clone()),
+                    value.map(|v| Arc::try_unwrap(v.value).unwrap_or_else(|arc| (*arc).clone())),
+                )
+            })
+            .collect();
+        let index_reader = ZkIndexReader {
+            storage: self,
+            witness,
+        };
+        let index_updates = key_index::update_index(
+            writes
+                .iter()
+                .map(|(key, value)| (key.clone(), value.is_some()))
+                .collect(),
+            &index_reader,
+        )?;
+
+        // Compute the jmt update from the write batch and the updates of the key index
+        let batch = writes
+            .into_iter()
+            .chain(index_updates)
+            .map(|(key, value)| (KeyHash(S::Hasher::hash(&key)), value));
 
         let next_version = latest_version + 1;
         let jmt = JellyfishMerkleTree::<_, S::Hasher>::new(&reader);